@@ -13,8 +13,10 @@ use lazy_static::lazy_static;
 use rand::random;
 use serde::{Deserialize, Serialize};
 
+use overlord::election::RoundRobin;
 use overlord::error::ConsensusError;
-use overlord::types::{Commit, Hash, Node, OverlordMsg, Status};
+use overlord::runtime::TokioSpawner;
+use overlord::types::{Commit, Evidence, Hash, Node, OverlordMsg, PeerPenalty, Status};
 use overlord::{Codec, Consensus, Crypto, DurationConfig, Overlord, OverlordHandler, Wal};
 
 lazy_static! {
@@ -47,11 +49,11 @@ macro_rules! impl_codec_for {
     ($($struc: ident),+) => {
         $(
             impl Codec for $struc {
-                fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+                fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
                     Ok(Bytes::from(bincode::serialize(&self.inner).unwrap()))
                 }
 
-                fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+                fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
                     let data: Option<Bytes> = bincode::deserialize(&data).unwrap();
                     Ok($struc { inner: data.unwrap() })
                 }
@@ -76,12 +78,12 @@ impl MockWal {
 
 #[async_trait]
 impl Wal for MockWal {
-    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send>> {
+    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send + Sync>> {
         *self.inner.lock().unwrap() = Some(info);
         Ok(())
     }
 
-    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send>> {
+    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send + Sync>> {
         Ok(self.inner.lock().unwrap().as_ref().cloned())
     }
 }
@@ -101,7 +103,7 @@ impl Crypto for MockCrypto {
         hash(&speech)
     }
 
-    fn sign(&self, _hash: Bytes) -> Result<Bytes, Box<dyn Error + Send>> {
+    fn sign(&self, _hash: Bytes) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
         Ok(self.name.clone())
     }
 
@@ -109,7 +111,7 @@ impl Crypto for MockCrypto {
         &self,
         _signatures: Vec<Bytes>,
         _speaker: Vec<Bytes>,
-    ) -> Result<Bytes, Box<dyn Error + Send>> {
+    ) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
         Ok(Bytes::new())
     }
 
@@ -118,7 +120,7 @@ impl Crypto for MockCrypto {
         _signature: Bytes,
         _hash: Bytes,
         _voter: Bytes,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -127,7 +129,7 @@ impl Crypto for MockCrypto {
         _aggregated_signature: Bytes,
         _hash: Bytes,
         _voters: Vec<Bytes>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
@@ -161,7 +163,7 @@ impl Consensus<Speech> for Brain {
         &self,
         _ctx: Context,
         _height: u64,
-    ) -> Result<(Speech, Hash), Box<dyn Error + Send>> {
+    ) -> Result<(Speech, Hash), Box<dyn Error + Send + Sync>> {
         let thought = gen_random_bytes();
         Ok((Speech::from(thought.clone()), hash(&thought)))
     }
@@ -172,7 +174,7 @@ impl Consensus<Speech> for Brain {
         _height: u64,
         _hash: Hash,
         _speech: Speech,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -181,7 +183,7 @@ impl Consensus<Speech> for Brain {
         _ctx: Context,
         height: u64,
         commit: Commit<Speech>,
-    ) -> Result<Status, Box<dyn Error + Send>> {
+    ) -> Result<Status, Box<dyn Error + Send + Sync>> {
         let mut speeches = self.consensus_speech.lock().unwrap();
         if let Some(speech) = speeches.get(&commit.height) {
             assert_eq!(speech, &commit.content.inner);
@@ -194,19 +196,19 @@ impl Consensus<Speech> for Brain {
             speeches.insert(commit.height, commit.content.inner);
         }
 
-        Ok(Status {
-            height:         height + 1,
-            interval:       Some(SPEECH_INTERVAL),
-            timer_config:   None,
-            authority_list: self.speaker_list.clone(),
-        })
+        Ok(Status::new(
+            height + 1,
+            Some(SPEECH_INTERVAL),
+            None,
+            self.speaker_list.clone(),
+        ))
     }
 
     async fn get_authority_list(
         &self,
         _ctx: Context,
         _height: u64,
-    ) -> Result<Vec<Node>, Box<dyn Error + Send>> {
+    ) -> Result<Vec<Node>, Box<dyn Error + Send + Sync>> {
         Ok(self.speaker_list.clone())
     }
 
@@ -214,7 +216,7 @@ impl Consensus<Speech> for Brain {
         &self,
         _ctx: Context,
         words: OverlordMsg<Speech>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         self.talk_to.iter().for_each(|(_, mouth)| {
             mouth.send(words.clone()).unwrap();
         });
@@ -226,12 +228,16 @@ impl Consensus<Speech> for Brain {
         _ctx: Context,
         name: Bytes,
         words: OverlordMsg<Speech>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         self.talk_to.get(&name).unwrap().send(words).unwrap();
         Ok(())
     }
 
     fn report_error(&self, _ctx: Context, _err: ConsensusError) {}
+
+    fn report_evidence(&self, _ctx: Context, _evidence: Evidence<Speech>) {}
+
+    fn report_penalty(&self, _ctx: Context, _penalty: PeerPenalty) {}
 }
 
 struct Speaker {
@@ -260,18 +266,15 @@ impl Speaker {
             Arc::clone(&brain),
             Arc::new(crypto),
             Arc::new(MockWal::new()),
+            Arc::new(RoundRobin),
+            Arc::new(TokioSpawner),
         );
         let overlord_handler = overlord.get_handler();
 
         overlord_handler
             .send_msg(
                 Context::new(),
-                OverlordMsg::RichStatus(Status {
-                    height:         1,
-                    interval:       Some(SPEECH_INTERVAL),
-                    timer_config:   None,
-                    authority_list: speaker_list,
-                }),
+                OverlordMsg::RichStatus(Status::new(1, Some(SPEECH_INTERVAL), None, speaker_list)),
             )
             .unwrap();
 
@@ -287,7 +290,7 @@ impl Speaker {
         interval: u64,
         timer_config: Option<DurationConfig>,
         speaker_list: Vec<Node>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let brain = Arc::<Brain>::clone(&self.brain);
         let handler = self.handler.clone();
 
@@ -0,0 +1,6 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use overlord::types::AggregatedSignature;
+use overlord_fuzz::decode;
+
+fuzz_target!(|data: &[u8]| decode::<AggregatedSignature>(data));
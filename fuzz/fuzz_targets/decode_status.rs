@@ -0,0 +1,6 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use overlord::types::Status;
+use overlord_fuzz::decode;
+
+fuzz_target!(|data: &[u8]| decode::<Status>(data));
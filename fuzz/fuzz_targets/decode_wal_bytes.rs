@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use overlord::types::Node;
+use overlord::WalInfo;
+use overlord_fuzz::Blob;
+
+fuzz_target!(|data: &[u8]| {
+    let authority_list = vec![
+        Node::new(vec![1u8; 32].into()),
+        Node::new(vec![2u8; 32].into()),
+    ];
+    let _ = WalInfo::<Blob>::from_wal_bytes(data, &authority_list);
+});
@@ -0,0 +1,6 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use overlord::WalInfo;
+use overlord_fuzz::{decode, Blob};
+
+fuzz_target!(|data: &[u8]| decode::<WalInfo<Blob>>(data));
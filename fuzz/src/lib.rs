@@ -0,0 +1,23 @@
+use std::error::Error;
+
+use bytes::Bytes;
+use overlord::Codec;
+
+/// A content whose codec never fails, so that the fuzzing reaches the RLP decoding of overlord.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Blob(pub Bytes);
+
+impl Codec for Blob {
+    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+        Ok(self.0.clone())
+    }
+
+    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Blob(data))
+    }
+}
+
+/// Decode the data as the type, which should return an error rather than panic on any input.
+pub fn decode<T: rlp::Decodable>(data: &[u8]) {
+    let _ = rlp::decode::<T>(data);
+}
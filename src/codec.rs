@@ -1,13 +1,15 @@
+use std::convert::TryFrom;
+
 use bytes::Bytes;
 use rlp::{Decodable, DecoderError, Encodable, Prototype, Rlp, RlpStream};
 
 use crate::smr::smr_types::Step;
 use crate::types::{
-    Address, AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Commit, Hash, HashChoke,
-    Node, PoLC, Proof, Proposal, Signature, SignedChoke, SignedProposal, SignedVote, Status,
-    UpdateFrom, Vote, VoteType,
+    Address, AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Commit, Evidence, Hash,
+    HashChoke, Node, PoLC, Proof, Proposal, Signature, SignedChoke, SignedProposal, SignedVote,
+    Status, UpdateFrom, Vote, VoteType,
 };
-use crate::wal::{WalInfo, WalLock};
+use crate::wal::{WalInfo, WalLock, Watermark};
 use crate::{Codec, DurationConfig};
 
 // impl Encodable and Decodable trait for SignedProposal
@@ -19,6 +21,17 @@ impl<T: Codec> Encodable for SignedProposal<T> {
     }
 }
 
+impl<T: Codec> SignedProposal<T> {
+    /// Encode the signed proposal with the content encoded in advance, so that an error of
+    /// `Codec::encode()` is returned by the caller rather than panics in `rlp_append()`.
+    pub(crate) fn rlp_bytes_with_content(&self, content: &Bytes) -> Vec<u8> {
+        let mut s = RlpStream::new();
+        s.begin_list(2).append(&self.signature.to_vec());
+        self.proposal.append_with_content(&mut s, content);
+        s.out()
+    }
+}
+
 impl<T: Codec> Decodable for SignedProposal<T> {
     fn decode(r: &Rlp) -> Result<Self, DecoderError> {
         match r.prototype()? {
@@ -39,14 +52,20 @@ impl<T: Codec> Decodable for SignedProposal<T> {
 // impl Encodable and Decodable trait for Proposal
 impl<T: Codec> Encodable for Proposal<T> {
     fn rlp_append(&self, s: &mut RlpStream) {
-        let content = self.content.encode().unwrap().to_vec();
+        let content = self.content.encode().unwrap();
+        self.append_with_content(s, &content);
+    }
+}
+
+impl<T: Codec> Proposal<T> {
+    fn append_with_content(&self, s: &mut RlpStream, content: &Bytes) {
         s.begin_list(6)
             .append(&self.height)
             .append(&self.round)
             .append(&self.block_hash.to_vec())
             .append(&self.lock)
             .append(&self.proposer.to_vec())
-            .append(&content);
+            .append(&content.to_vec());
     }
 }
 
@@ -150,7 +169,7 @@ impl Decodable for AggregatedVote {
             Prototype::List(6) => {
                 let signature: AggregatedSignature = r.val_at(0)?;
                 let tmp: u8 = r.val_at(1)?;
-                let vote_type = VoteType::from(tmp);
+                let vote_type = VoteType::try_from(tmp)?;
                 let height: u64 = r.val_at(2)?;
                 let round: u64 = r.val_at(3)?;
                 let tmp: Vec<u8> = r.val_at(4)?;
@@ -220,7 +239,7 @@ impl Decodable for Vote {
                 let height: u64 = r.val_at(0)?;
                 let round: u64 = r.val_at(1)?;
                 let tmp: u8 = r.val_at(2)?;
-                let vote_type = VoteType::from(tmp);
+                let vote_type = VoteType::try_from(tmp)?;
                 let tmp: Vec<u8> = r.val_at(3)?;
                 let block_hash = Hash::from(tmp);
                 Ok(Vote {
@@ -342,18 +361,21 @@ impl Encodable for Status {
         } else {
             self.timer_config.clone().unwrap()
         };
-        s.begin_list(4)
+        let prev_hash = self.prev_hash.clone().unwrap_or_default();
+        s.begin_list(5)
             .append(&self.height)
             .append(&interval)
             .append(&config)
-            .append_list(&self.authority_list);
+            .append_list(&self.authority_list)
+            .append(&prev_hash.to_vec());
     }
 }
 
 impl Decodable for Status {
     fn decode(r: &Rlp) -> Result<Self, DecoderError> {
         match r.prototype()? {
-            Prototype::List(4) => {
+            // A status without the previous block hash is encoded as a list of 4 items.
+            Prototype::List(len) if len == 4 || len == 5 => {
                 let height: u64 = r.val_at(0)?;
                 let tmp: u64 = r.val_at(1)?;
                 let interval = if tmp == 0 { None } else { Some(tmp) };
@@ -364,12 +386,19 @@ impl Decodable for Status {
                     Some(tmp)
                 };
                 let authority_list: Vec<Node> = r.list_at(3)?;
+                let prev_hash = if len == 5 {
+                    let tmp: Vec<u8> = r.val_at(4)?;
+                    Some(Hash::from(tmp)).filter(|hash| !hash.is_empty())
+                } else {
+                    None
+                };
 
                 Ok(Status {
                     height,
                     interval,
                     timer_config,
                     authority_list,
+                    prev_hash,
                 })
             }
             _ => Err(DecoderError::RlpInconsistentLengthAndData),
@@ -441,7 +470,7 @@ impl Decodable for UpdateFrom {
                         let qc: AggregatedChoke = r.val_at(1)?;
                         UpdateFrom::ChokeQC(qc)
                     }
-                    _ => unreachable!(),
+                    _ => return Err(DecoderError::Custom("Invalid update from type")),
                 };
                 Ok(res)
             }
@@ -482,31 +511,66 @@ impl<T: Codec> Decodable for WalLock<T> {
 
 impl<T: Codec> Encodable for WalInfo<T> {
     fn rlp_append(&self, s: &mut RlpStream) {
-        s.begin_list(5)
+        s.begin_list(6)
             .append(&self.height)
             .append(&self.round)
             .append::<u8>(&self.step.clone().into())
             .append(&self.lock)
-            .append(&self.from);
+            .append(&self.from)
+            .append(&self.watermark);
     }
 }
 
 impl<T: Codec> Decodable for WalInfo<T> {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        // The wal information of version 1 does not contain the watermark.
+        let watermark = match r.prototype()? {
+            Prototype::List(5) => None,
+            Prototype::List(6) => r.val_at(5)?,
+            _ => return Err(DecoderError::RlpInconsistentLengthAndData),
+        };
+
+        let height: u64 = r.val_at(0)?;
+        let round: u64 = r.val_at(1)?;
+        let tmp: u8 = r.val_at(2)?;
+        let step = Step::try_from(tmp)?;
+        let lock = r.val_at(3)?;
+        let from: UpdateFrom = r.val_at(4)?;
+        Ok(WalInfo {
+            height,
+            round,
+            step,
+            lock,
+            from,
+            watermark,
+        })
+    }
+}
+
+impl Encodable for Watermark {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(4)
+            .append(&self.height)
+            .append(&self.round)
+            .append::<u8>(&self.step.clone().into())
+            .append(&self.hash.to_vec());
+    }
+}
+
+impl Decodable for Watermark {
     fn decode(r: &Rlp) -> Result<Self, DecoderError> {
         match r.prototype()? {
-            Prototype::List(5) => {
+            Prototype::List(4) => {
                 let height: u64 = r.val_at(0)?;
                 let round: u64 = r.val_at(1)?;
                 let tmp: u8 = r.val_at(2)?;
-                let step = Step::from(tmp);
-                let lock = r.val_at(3)?;
-                let from: UpdateFrom = r.val_at(4)?;
-                Ok(WalInfo {
+                let step = Step::try_from(tmp)?;
+                let tmp: Vec<u8> = r.val_at(3)?;
+                Ok(Watermark {
                     height,
                     round,
                     step,
-                    lock,
-                    from,
+                    hash: Hash::from(tmp),
                 })
             }
             _ => Err(DecoderError::RlpInconsistentLengthAndData),
@@ -572,34 +636,24 @@ impl Decodable for SignedChoke {
 
 impl Encodable for AggregatedChoke {
     fn rlp_append(&self, s: &mut RlpStream) {
-        s.begin_list(4)
+        s.begin_list(3)
             .append(&self.height)
             .append(&self.round)
-            .append(&self.signature.to_vec());
-        let tmp: Vec<Vec<u8>> = self
-            .voters
-            .iter()
-            .map(|addr| addr.to_vec())
-            .collect::<Vec<_>>();
-        s.append_list::<Vec<u8>, _>(&tmp);
+            .append(&self.signature);
     }
 }
 
 impl Decodable for AggregatedChoke {
     fn decode(r: &Rlp) -> Result<Self, DecoderError> {
         match r.prototype()? {
-            Prototype::List(4) => {
+            Prototype::List(3) => {
                 let height: u64 = r.val_at(0)?;
                 let round: u64 = r.val_at(1)?;
-                let tmp: Vec<u8> = r.val_at(2)?;
-                let signature = Signature::from(tmp);
-                let tmp: Vec<Vec<u8>> = r.list_at(3)?;
-                let voters = tmp.into_iter().map(Address::from).collect::<Vec<_>>();
+                let signature: AggregatedSignature = r.val_at(2)?;
                 Ok(AggregatedChoke {
                     height,
                     round,
                     signature,
-                    voters,
                 })
             }
             _ => Err(DecoderError::RlpInconsistentLengthAndData),
@@ -613,14 +667,57 @@ impl Encodable for HashChoke {
     }
 }
 
+// impl Encodable and Decodable trait for Evidence
+impl<T: Codec> Encodable for Evidence<T> {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3);
+        match self {
+            Evidence::DoubleProposal(first, second) => {
+                s.append(&0u8).append(first).append(second);
+            }
+            Evidence::DoubleVote(first, second) => {
+                s.append(&1u8).append(first).append(second);
+            }
+        }
+    }
+}
+
+impl<T: Codec> Decodable for Evidence<T> {
+    fn decode(r: &Rlp) -> Result<Self, DecoderError> {
+        match r.prototype()? {
+            Prototype::List(3) => {
+                let tmp: u8 = r.val_at(0)?;
+                match tmp {
+                    0u8 => {
+                        let first: SignedProposal<T> = r.val_at(1)?;
+                        let second: SignedProposal<T> = r.val_at(2)?;
+                        Ok(Evidence::DoubleProposal(first, second))
+                    }
+                    1u8 => {
+                        let first: SignedVote = r.val_at(1)?;
+                        let second: SignedVote = r.val_at(2)?;
+                        Ok(Evidence::DoubleVote(first, second))
+                    }
+                    _ => Err(DecoderError::Custom("Invalid evidence type")),
+                }
+            }
+            _ => Err(DecoderError::RlpInconsistentLengthAndData),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::error::Error;
+    use std::fmt::Debug;
 
     use bincode::{deserialize, serialize};
     use rand::random;
+    use serde::de::DeserializeOwned;
     use serde::{Deserialize, Serialize};
 
+    use crate::types::OverlordMsg;
+
     use super::*;
 
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -630,12 +727,12 @@ mod test {
     }
 
     impl Codec for Pill {
-        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
             let encode: Vec<u8> = serialize(&self).expect("Serialize Pill error");
             Ok(Bytes::from(encode))
         }
 
-        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
             let decode: Pill = deserialize(&data.as_ref()).expect("Deserialize Pill error.");
             Ok(decode)
         }
@@ -649,6 +746,44 @@ mod test {
         }
     }
 
+    /// A content whose codec never fails, so that a malformed message can only be rejected by the
+    /// RLP decoding.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Blob(Bytes);
+
+    impl Codec for Blob {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+            Ok(self.0.clone())
+        }
+
+        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
+            Ok(Blob(data))
+        }
+    }
+
+    impl Blob {
+        fn new() -> Self {
+            Blob(Bytes::from(
+                (0..16).map(|_| random::<u8>()).collect::<Vec<_>>(),
+            ))
+        }
+    }
+
+    /// Decode the truncated and mutated encoding of a message, which should never panic.
+    fn decode_malformed<D: Decodable>(data: &[u8]) {
+        for len in 0..data.len() {
+            let _ = rlp::decode::<D>(&data[..len]);
+        }
+
+        for i in 0..data.len() {
+            for byte in [0x00, 0x03, 0x7f, 0x80, 0xb8, 0xbf, 0xc0, 0xf8, 0xff].iter() {
+                let mut malformed = data.to_vec();
+                malformed[i] = *byte;
+                let _ = rlp::decode::<D>(&malformed);
+            }
+        }
+    }
+
     impl<T: Codec> SignedProposal<T> {
         fn new(content: T, lock: Option<PoLC>) -> Self {
             SignedProposal {
@@ -698,7 +833,7 @@ mod test {
         fn new(vote_type: u8) -> Self {
             AggregatedVote {
                 signature:  gen_aggr_signature(),
-                vote_type:  VoteType::from(vote_type),
+                vote_type:  VoteType::try_from(vote_type).unwrap(),
                 height:     random::<u64>(),
                 round:      random::<u64>(),
                 block_hash: gen_hash(),
@@ -712,7 +847,7 @@ mod test {
             Vote {
                 height:     random::<u64>(),
                 round:      random::<u64>(),
-                vote_type:  VoteType::from(vote_type),
+                vote_type:  VoteType::try_from(vote_type).unwrap(),
                 block_hash: gen_hash(),
             }
         }
@@ -746,8 +881,7 @@ mod test {
             AggregatedChoke {
                 height:    random::<u64>(),
                 round:     random::<u64>(),
-                signature: gen_signature(),
-                voters:    vec![gen_address(), gen_address()],
+                signature: gen_aggr_signature(),
             }
         }
     }
@@ -773,7 +907,7 @@ mod test {
     }
 
     impl Status {
-        fn new(time: Option<u64>, is_update_config: bool) -> Self {
+        fn mock(time: Option<u64>, is_update_config: bool) -> Self {
             let config = if is_update_config {
                 Some(DurationConfig {
                     propose_ratio:   random::<u64>(),
@@ -790,6 +924,7 @@ mod test {
                 interval:       time,
                 timer_config:   config,
                 authority_list: vec![Node::new(gen_address())],
+                prev_hash:      time.map(|_| gen_hash()),
             }
         }
     }
@@ -811,12 +946,19 @@ mod test {
             let round = random::<u64>();
             let step = Step::Precommit;
             let from = UpdateFrom::ChokeQC(AggregatedChoke::new());
+            let watermark = Some(Watermark {
+                height,
+                round,
+                step: Step::Prevote,
+                hash: gen_hash(),
+            });
             WalInfo {
                 height,
                 round,
                 step,
                 lock,
                 from,
+                watermark,
             }
         }
     }
@@ -884,12 +1026,12 @@ mod test {
         assert_eq!(commit, res);
 
         // Test Status
-        let status = Status::new(None, true);
+        let status = Status::mock(None, true);
         let res: Status = rlp::decode(&status.rlp_bytes()).unwrap();
         assert_eq!(status, res);
 
         // Test Status
-        let status = Status::new(Some(3000), false);
+        let status = Status::mock(Some(3000), false);
         let res: Status = rlp::decode(&status.rlp_bytes()).unwrap();
         assert_eq!(status, res);
 
@@ -920,5 +1062,124 @@ mod test {
         let wal_info = WalInfo::new(None);
         let res: WalInfo<Pill> = rlp::decode(&wal_info.rlp_bytes()).unwrap();
         assert_eq!(wal_info, res);
+
+        // Test Evidence
+        let evidence = Evidence::DoubleProposal(
+            SignedProposal::new(Pill::new(), Some(PoLC::new())),
+            SignedProposal::new(Pill::new(), None),
+        );
+        let res: Evidence<Pill> = rlp::decode(&evidence.rlp_bytes()).unwrap();
+        assert_eq!(evidence, res);
+
+        let evidence: Evidence<Pill> =
+            Evidence::DoubleVote(SignedVote::new(1u8), SignedVote::new(1u8));
+        let res: Evidence<Pill> = rlp::decode(&evidence.rlp_bytes()).unwrap();
+        assert_eq!(evidence, res);
+    }
+
+    #[test]
+    fn test_decode_invalid_value() {
+        use crate::smr::smr_types::TriggerType;
+        use crate::types::Role;
+
+        assert!(Role::try_from(2).is_err());
+        assert!(VoteType::try_from(0).is_err());
+        assert!(VoteType::try_from(3).is_err());
+        assert!(Step::try_from(5).is_err());
+        assert!(TriggerType::try_from(3).is_err());
+
+        let mut s = RlpStream::new_list(4);
+        s.append(&1u64)
+            .append(&0u64)
+            .append(&3u8)
+            .append(&gen_hash().to_vec());
+        assert!(rlp::decode::<Vote>(&s.out()).is_err());
+
+        let mut s = RlpStream::new_list(4);
+        s.append(&1u64)
+            .append(&0u64)
+            .append(&9u8)
+            .append(&gen_hash().to_vec());
+        assert!(rlp::decode::<Watermark>(&s.out()).is_err());
+
+        let mut s = RlpStream::new_list(2);
+        s.append(&3u8).append(&AggregatedVote::new(1u8));
+        assert!(rlp::decode::<UpdateFrom>(&s.out()).is_err());
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        let from = UpdateFrom::PrevoteQC(AggregatedVote::new(1u8));
+        decode_malformed::<SignedProposal<Blob>>(
+            &SignedProposal::new(Blob::new(), Some(PoLC::new())).rlp_bytes(),
+        );
+        decode_malformed::<SignedVote>(&SignedVote::new(2u8).rlp_bytes());
+        decode_malformed::<AggregatedVote>(&AggregatedVote::new(1u8).rlp_bytes());
+        decode_malformed::<SignedChoke>(&SignedChoke::new(from).rlp_bytes());
+        decode_malformed::<AggregatedChoke>(&AggregatedChoke::new().rlp_bytes());
+        decode_malformed::<WalInfo<Blob>>(&WalInfo::new(Some(Blob::new())).rlp_bytes());
+    }
+
+    #[test]
+    fn test_evidence_serde() {
+        let evidence = Evidence::DoubleProposal(
+            SignedProposal::new(Pill::new(), Some(PoLC::new())),
+            SignedProposal::new(Pill::new(), None),
+        );
+        let json = serde_json::to_string(&evidence).unwrap();
+        let res: Evidence<Pill> = serde_json::from_str(&json).unwrap();
+        assert_eq!(evidence, res);
+
+        let evidence: Evidence<Pill> =
+            Evidence::DoubleVote(SignedVote::new(2u8), SignedVote::new(2u8));
+        let json = serde_json::to_string(&evidence).unwrap();
+        let res: Evidence<Pill> = serde_json::from_str(&json).unwrap();
+        assert_eq!(evidence, res);
+    }
+
+    /// Check that the message survives a JSON round trip, and that the message decoded from JSON
+    /// has the same RLP encoding as the original one.
+    fn json_rlp_agree<M>(msg: M)
+    where
+        M: Serialize + DeserializeOwned + Encodable + Decodable + Debug + PartialEq,
+    {
+        let json = serde_json::to_string(&msg).unwrap();
+        let from_json: M = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json, msg);
+
+        let from_rlp: M = rlp::decode(&msg.rlp_bytes()).unwrap();
+        assert_eq!(from_rlp, msg);
+        assert_eq!(from_json.rlp_bytes(), msg.rlp_bytes());
+    }
+
+    #[test]
+    fn test_types_serde() {
+        json_rlp_agree(SignedProposal::new(Pill::new(), Some(PoLC::new())));
+        json_rlp_agree(SignedProposal::new(Pill::new(), None));
+        json_rlp_agree(Proposal::new(Pill::new(), Some(PoLC::new())));
+        json_rlp_agree(PoLC::new());
+        json_rlp_agree(SignedVote::new(1u8));
+        json_rlp_agree(Vote::new(2u8));
+        json_rlp_agree(AggregatedVote::new(2u8));
+        json_rlp_agree(Commit::new(Pill::new()));
+        json_rlp_agree(Proof::new());
+        json_rlp_agree(Status::mock(None, true));
+        json_rlp_agree(Status::mock(Some(3000), false));
+        json_rlp_agree(AggregatedChoke::new());
+        json_rlp_agree(Choke::new(UpdateFrom::PrevoteQC(AggregatedVote::new(1u8))));
+        json_rlp_agree(SignedChoke::new(
+            UpdateFrom::ChokeQC(AggregatedChoke::new()),
+        ));
+
+        // Byte fields are hex strings in JSON.
+        let signed_choke = SignedChoke::new(UpdateFrom::PrecommitQC(AggregatedVote::new(2u8)));
+        let json = serde_json::to_value(&signed_choke).unwrap();
+        assert_eq!(json["signature"], hex::encode(&signed_choke.signature));
+        assert_eq!(json["address"], hex::encode(&signed_choke.address));
+
+        let msg = OverlordMsg::SignedChoke(signed_choke);
+        let json = serde_json::to_string(&msg).unwrap();
+        let res: OverlordMsg<Pill> = serde_json::from_str(&json).unwrap();
+        assert_eq!(msg, res);
     }
 }
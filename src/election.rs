@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use prime_tools::get_primes_less_than_x;
+
+use crate::error::ConsensusError;
+use crate::types::{Address, Hash, Node};
+use crate::utils::rand_proposer::get_random_proposer_index;
+use crate::{ConsensusResult, ProposerElection};
+
+/// The number of recent block hashes that `PrevHashSeeded` keeps as seeds.
+const MAX_SEED_CACHE: usize = 16;
+
+/// The default proposer election, which is `WeightedRandom` with the `random_leader` feature,
+/// otherwise `RoundRobin`.
+pub fn default_election() -> Arc<dyn ProposerElection> {
+    if cfg!(feature = "random_leader") {
+        Arc::new(WeightedRandom)
+    } else {
+        Arc::new(RoundRobin)
+    }
+}
+
+/// Round robin election. The proposer index moves with a prime stride as the height grows and
+/// moves one step as the round grows.
+#[derive(Clone, Debug, Default)]
+pub struct RoundRobin;
+
+impl ProposerElection for RoundRobin {
+    fn elect(&self, height: u64, round: u64, authority_list: &[Node]) -> ConsensusResult<Address> {
+        let len = check_authority_list(authority_list)?.len();
+        let prime_num = *get_primes_less_than_x(len as u32).last().unwrap_or(&1) as u64;
+        let index = (height * prime_num + round) % (len as u64);
+        Ok(authority_list[index as usize].address.clone())
+    }
+}
+
+/// Weighted random election. The proposer is chosen randomly by propose weight, and the random
+/// seed is `height + round`.
+#[derive(Clone, Debug, Default)]
+pub struct WeightedRandom;
+
+impl ProposerElection for WeightedRandom {
+    fn elect(&self, height: u64, round: u64, authority_list: &[Node]) -> ConsensusResult<Address> {
+        elect_by_weight(height.wrapping_add(round), authority_list)
+    }
+}
+
+/// Weighted random election seeded by the hash of the previous block. The proposer of a height is
+/// chosen randomly by propose weight, and the random seed is derived from the block hash of the
+/// previous height and the round. The seeds are recorded on commit and from the `prev_hash` of
+/// each `Status`, so the application must fill `prev_hash` in every status it gives to overlord.
+/// When overlord restarts from the wal, the application should set the seed of the previous
+/// height by `set_seed()` before running. Electing without the seed returns an error.
+#[derive(Debug, Default)]
+pub struct PrevHashSeeded {
+    seeds: RwLock<BTreeMap<u64, Hash>>,
+}
+
+impl PrevHashSeeded {
+    /// Create a new election without any seed.
+    pub fn new() -> Self {
+        PrevHashSeeded::default()
+    }
+
+    /// Set the block hash of the given height as the seed of the next height.
+    pub fn set_seed(&self, height: u64, block_hash: Hash) {
+        let mut seeds = self.seeds.write();
+        seeds.insert(height, block_hash);
+        while seeds.len() > MAX_SEED_CACHE {
+            let lowest = *seeds.keys().next().unwrap();
+            seeds.remove(&lowest);
+        }
+    }
+
+    fn get_seed(&self, height: u64) -> Option<u64> {
+        let seeds = self.seeds.read();
+        seeds.get(&height.checked_sub(1)?).map(|hash| {
+            let mut seed = [0u8; 8];
+            for (index, byte) in hash.iter().enumerate() {
+                seed[index % 8] ^= *byte;
+            }
+            u64::from_le_bytes(seed)
+        })
+    }
+}
+
+impl ProposerElection for PrevHashSeeded {
+    fn elect(&self, height: u64, round: u64, authority_list: &[Node]) -> ConsensusResult<Address> {
+        let seed = self.get_seed(height).ok_or_else(|| {
+            ConsensusError::Other(format!(
+                "No previous block hash to seed the election of height {}",
+                height
+            ))
+        })?;
+        elect_by_weight(seed.wrapping_add(round), authority_list)
+    }
+
+    fn on_commit(&self, height: u64, block_hash: &Hash) {
+        self.set_seed(height, block_hash.clone());
+    }
+}
+
+fn check_authority_list(authority_list: &[Node]) -> ConsensusResult<&[Node]> {
+    if authority_list.is_empty() {
+        return Err(ConsensusError::Other("Empty authority list".to_string()));
+    }
+    Ok(authority_list)
+}
+
+fn elect_by_weight(seed: u64, authority_list: &[Node]) -> ConsensusResult<Address> {
+    let weights = check_authority_list(authority_list)?
+        .iter()
+        .map(|node| u64::from(node.propose_weight))
+        .collect::<Vec<_>>();
+    let weight_sum = weights.iter().sum::<u64>();
+    if weight_sum == 0 {
+        return Err(ConsensusError::Other(
+            "The sum of propose weights is zero".to_string(),
+        ));
+    }
+
+    let index = get_random_proposer_index(seed, &weights, weight_sum);
+    Ok(authority_list[index].address.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use rand::random;
+
+    use crate::types::{Address, Hash, Node};
+    use crate::ProposerElection;
+
+    use super::{PrevHashSeeded, RoundRobin, WeightedRandom};
+
+    fn gen_address() -> Address {
+        Address::from((0..32).map(|_| random::<u8>()).collect::<Vec<_>>())
+    }
+
+    fn gen_auth_list(len: usize) -> Vec<Node> {
+        let mut authority_list = (0..len)
+            .map(|_| Node::new(gen_address()))
+            .collect::<Vec<_>>();
+        authority_list.sort();
+        authority_list
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let authority_list = gen_auth_list(4);
+        let election = RoundRobin;
+        let expect = vec![
+            (1, 0, 3),
+            (1, 1, 0),
+            (2, 0, 2),
+            (2, 2, 0),
+            (3, 0, 1),
+            (3, 1, 2),
+        ];
+
+        for (height, round, index) in expect.into_iter() {
+            assert_eq!(
+                election.elect(height, round, &authority_list).unwrap(),
+                authority_list[index].address
+            );
+        }
+        assert!(election.elect(1, 0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_weighted_random() {
+        let mut authority_list = gen_auth_list(4);
+        let election = WeightedRandom;
+        let expect = vec![3, 2, 0, 0, 3, 1, 2, 2, 0];
+
+        for (seed, index) in expect.into_iter().enumerate() {
+            assert_eq!(
+                election.elect(seed as u64 + 1, 0, &authority_list).unwrap(),
+                authority_list[index].address
+            );
+        }
+
+        // Only the node with propose weight can be elected.
+        for node in authority_list.iter_mut() {
+            node.set_propose_weight(0);
+        }
+        assert!(election.elect(1, 0, &authority_list).is_err());
+        authority_list[2].set_propose_weight(1);
+        for height in 0..10 {
+            assert_eq!(
+                election.elect(height, 0, &authority_list).unwrap(),
+                authority_list[2].address
+            );
+        }
+    }
+
+    #[test]
+    fn test_prev_hash_seeded() {
+        let authority_list = gen_auth_list(10);
+        let election = PrevHashSeeded::new();
+
+        // Electing without a seed is an error.
+        for height in 0..10 {
+            assert!(election.elect(height, 0, &authority_list).is_err());
+        }
+
+        // The same seed elects the same proposer.
+        let hash = Hash::from((0..32).map(|_| random::<u8>()).collect::<Vec<_>>());
+        let other = PrevHashSeeded::new();
+        election.on_commit(1, &hash);
+        other.set_seed(1, hash);
+        for round in 0..10 {
+            assert_eq!(
+                election.elect(2, round, &authority_list).unwrap(),
+                other.elect(2, round, &authority_list).unwrap()
+            );
+        }
+
+        // Only recent seeds are kept.
+        for height in 0..100 {
+            election.set_seed(height, Bytes::from(vec![height as u8]));
+        }
+        assert_eq!(election.seeds.read().len(), super::MAX_SEED_CACHE);
+        assert!(election.get_seed(50).is_none());
+        assert!(election.get_seed(99).is_some());
+    }
+}
@@ -4,12 +4,57 @@ use std::error::Error;
 
 use derive_more::Display;
 
-/// Overlord consensus error.
-#[derive(Clone, Debug, Display)]
+use crate::types::Address;
+
+/// The kind of a consensus error, which tells whether a peer, the application or the node itself
+/// is to blame.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// A peer sends a message with an invalid signature or aggregated signature.
+    #[display(fmt = "Invalid signature")]
+    InvalidSignature,
+    /// A peer sends a message signed by an address which is not in the authority list.
+    #[display(fmt = "Unknown voter")]
+    UnknownVoter,
+    /// A peer sends a malformed, oversized or conflicting message.
+    #[display(fmt = "Invalid message")]
+    InvalidMessage,
+    /// The application returns an error by the `Consensus` trait.
+    #[display(fmt = "Application")]
+    Application,
+    /// The crypto or the signer fails to sign or aggregate.
+    #[display(fmt = "Crypto")]
+    Crypto,
+    /// The wal fails to save or load.
+    #[display(fmt = "Storage")]
+    Storage,
+    /// Signing a message is refused since it may cause a double sign.
+    #[display(fmt = "Double sign")]
+    DoubleSign,
+    /// An internal error of overlord, such as a closed channel or an unexpected state.
+    #[display(fmt = "Internal")]
+    Internal,
+}
+
+impl ErrorKind {
+    /// Whether the error is caused by a message from a peer.
+    pub fn is_peer_fault(self) -> bool {
+        match self {
+            ErrorKind::InvalidSignature | ErrorKind::UnknownVoter | ErrorKind::InvalidMessage => {
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Overlord consensus error. Use `kind()` to classify an error, and `height()`, `round()`,
+/// `address()` and `source()` to get the details.
+#[derive(Debug, Display)]
 pub enum ConsensusError {
-    ///
-    #[display(fmt = "Invalid address")]
-    InvalidAddress,
+    /// The address is not in the authority list.
+    #[display(fmt = "Invalid address {}", "hex::encode(_0)")]
+    InvalidAddress(Address),
     ///
     ChannelErr(String),
     ///
@@ -21,18 +66,76 @@ pub enum ConsensusError {
     ///
     #[display(fmt = "Throw {} event error", _0)]
     ThrowEventErr(String),
-    ///
-    #[display(fmt = "Proposal error {}", _0)]
-    ProposalErr(String),
-    ///
-    #[display(fmt = "Prevote error {}", _0)]
-    PrevoteErr(String),
-    ///
-    #[display(fmt = "Precommit error {}", _0)]
-    PrecommitErr(String),
-    ///
-    #[display(fmt = "Brake error {}", _0)]
-    BrakeErr(String),
+    /// The proposal of the height and round can not be made or handled.
+    #[display(
+        fmt = "Proposal error of height {}, round {}: {}",
+        height,
+        round,
+        reason
+    )]
+    ProposalErr {
+        /// Height of the proposal.
+        height: u64,
+        /// Round of the proposal.
+        round: u64,
+        /// The reason of the error.
+        reason: String,
+    },
+    /// The proposal is not signed by the proposer of the height and the round.
+    #[display(
+        fmt = "Invalid proposer {} of height {}, round {}",
+        "hex::encode(address)",
+        height,
+        round
+    )]
+    InvalidProposer {
+        /// Height of the proposal.
+        height: u64,
+        /// Round of the proposal.
+        round: u64,
+        /// Address of the proposal.
+        address: Address,
+    },
+    /// The prevote of the height and round can not be made or handled.
+    #[display(
+        fmt = "Prevote error of height {}, round {}: {}",
+        height,
+        round,
+        reason
+    )]
+    PrevoteErr {
+        /// Height of the prevote.
+        height: u64,
+        /// Round of the prevote.
+        round: u64,
+        /// The reason of the error.
+        reason: String,
+    },
+    /// The precommit of the height and round can not be made or handled.
+    #[display(
+        fmt = "Precommit error of height {}, round {}: {}",
+        height,
+        round,
+        reason
+    )]
+    PrecommitErr {
+        /// Height of the precommit.
+        height: u64,
+        /// Round of the precommit.
+        round: u64,
+        /// The reason of the error.
+        reason: String,
+    },
+    /// The brake of the height and round can not be made or handled.
+    #[display(fmt = "Brake error of height {}, round {}: {}", height, round, reason)]
+    BrakeErr {
+        /// Height of the brake.
+        height: u64,
+        /// Round of the brake.
+        round: u64,
+        /// The reason of the error.
+        reason: String,
+    },
     ///
     #[display(fmt = "Self round is {}, vote round is {}", local, vote)]
     RoundDiff {
@@ -41,6 +144,31 @@ pub enum ConsensusError {
         ///
         vote: u64,
     },
+    /// The proposal block does not pass the check of the application.
+    #[display(
+        fmt = "Check block of height {}, round {} error {}",
+        height,
+        round,
+        source
+    )]
+    CheckBlockErr {
+        /// Height of the block.
+        height: u64,
+        /// Round of the proposal.
+        round: u64,
+        /// The error returned by `Consensus::check_block()`.
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The application returns an error by a method of the `Consensus` trait.
+    #[display(fmt = "Consensus {} of height {} error {}", method, height, source)]
+    ApplicationErr {
+        /// The method of the `Consensus` trait.
+        method: &'static str,
+        /// Height of the call.
+        height: u64,
+        /// The error returned by the method.
+        source: Box<dyn Error + Send + Sync>,
+    },
     ///
     #[display(fmt = "Self check not pass {}", _0)]
     SelfCheckErr(String),
@@ -50,17 +178,35 @@ pub enum ConsensusError {
     ///
     #[display(fmt = "Timer error {}", _0)]
     TimerErr(String),
-    ///
+    /// The state is unexpected, such as lacking a quorum certificate or a block it should have.
     #[display(fmt = "State error {}", _0)]
     StateErr(String),
-    ///
-    #[display(fmt = "Multiple proposal in height {}, round {}", _0, _1)]
-    MultiProposal(u64, u64),
-    ///
+    /// The proposer sends different proposals of the same height and round.
+    #[display(
+        fmt = "Multiple proposal of {} in height {}, round {}",
+        "hex::encode(address)",
+        height,
+        round
+    )]
+    MultiProposal {
+        /// Height of the proposals.
+        height: u64,
+        /// Round of the proposals.
+        round: u64,
+        /// Address of the proposer.
+        address: Address,
+    },
+    /// The vote or proposal collector lacks the message.
     #[display(fmt = "Storage error {}", _0)]
     StorageErr(String),
     ///
-    #[display(fmt = "Save Wal error {}, {}, {} step", height, round, step)]
+    #[display(
+        fmt = "Save Wal error {}, {}, {} step, {}",
+        height,
+        round,
+        step,
+        source
+    )]
     SaveWalErr {
         ///
         height: u64,
@@ -68,22 +214,151 @@ pub enum ConsensusError {
         round: u64,
         ///
         step: String,
+        /// The error returned by `Wal::save()`.
+        source: Box<dyn Error + Send + Sync>,
     },
-    ///
+    /// The wal information is torn, corrupted or of an unknown version.
     #[display(fmt = "Load Wal error {}", _0)]
     LoadWalErr(String),
-    ///
-    #[display(fmt = "Crypto error {}", _0)]
-    CryptoErr(String),
-    ///
-    #[display(fmt = "Aggregated signature error {}", _0)]
-    AggregatedSignatureErr(String),
+    /// The wal fails to access the underlying storage.
+    #[display(fmt = "Wal error {}", _0)]
+    WalErr(Box<dyn Error + Send + Sync>),
+    /// The crypto or the signer fails to sign or aggregate.
+    #[display(fmt = "Crypto error of height {}, round {}: {}", height, round, source)]
+    CryptoErr {
+        /// Height of the message.
+        height: u64,
+        /// Round of the message.
+        round: u64,
+        /// The error returned by the crypto or the signer.
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// A signature or an aggregated signature fails the verification.
+    #[display(
+        fmt = "Invalid signature of height {}, round {}: {}",
+        height,
+        round,
+        source
+    )]
+    InvalidSignature {
+        /// Height of the message.
+        height: u64,
+        /// Round of the message.
+        round: u64,
+        /// The signer, `None` for an aggregated signature.
+        address: Option<Address>,
+        /// The error returned by the crypto.
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The voters of an aggregated signature are not above the threshold, or the aggregated
+    /// signature is for an invalid message. There is no single signer to blame.
+    #[display(
+        fmt = "Aggregated signature error of height {}, round {}: {}",
+        height,
+        round,
+        reason
+    )]
+    AggregatedSignatureErr {
+        /// Height of the quorum certificate.
+        height: u64,
+        /// Round of the quorum certificate.
+        round: u64,
+        /// The reason of the error.
+        reason: String,
+    },
+    /// The message on the wire is malformed, unsupported or oversized.
+    #[display(fmt = "Wire message error {}", _0)]
+    WireErr(String),
+    /// Signing the message may cause a double sign.
+    #[display(fmt = "Double sign error {}", _0)]
+    DoubleSignErr(String),
     /// Other error.
     #[display(fmt = "Other error {}", _0)]
     Other(String),
 }
 
-impl Error for ConsensusError {}
+impl ConsensusError {
+    /// Get the kind of the error.
+    pub fn kind(&self) -> ErrorKind {
+        use self::ConsensusError::*;
+        match self {
+            InvalidSignature { .. } | AggregatedSignatureErr { .. } => ErrorKind::InvalidSignature,
+            InvalidAddress(_) => ErrorKind::UnknownVoter,
+            InvalidProposer { .. } | MultiProposal { .. } | WireErr(_) => ErrorKind::InvalidMessage,
+            CheckBlockErr { .. } | ApplicationErr { .. } => ErrorKind::Application,
+            CryptoErr { .. } => ErrorKind::Crypto,
+            SaveWalErr { .. } | LoadWalErr(_) | WalErr(_) => ErrorKind::Storage,
+            DoubleSignErr(_) => ErrorKind::DoubleSign,
+            _ => ErrorKind::Internal,
+        }
+    }
+
+    /// Get the height of the message or the call which causes the error.
+    pub fn height(&self) -> Option<u64> {
+        use self::ConsensusError::*;
+        match self {
+            ProposalErr { height, .. }
+            | PrevoteErr { height, .. }
+            | PrecommitErr { height, .. }
+            | BrakeErr { height, .. }
+            | InvalidProposer { height, .. }
+            | CheckBlockErr { height, .. }
+            | ApplicationErr { height, .. }
+            | MultiProposal { height, .. }
+            | SaveWalErr { height, .. }
+            | CryptoErr { height, .. }
+            | InvalidSignature { height, .. }
+            | AggregatedSignatureErr { height, .. } => Some(*height),
+            _ => None,
+        }
+    }
+
+    /// Get the round of the message or the call which causes the error.
+    pub fn round(&self) -> Option<u64> {
+        use self::ConsensusError::*;
+        match self {
+            ProposalErr { round, .. }
+            | PrevoteErr { round, .. }
+            | PrecommitErr { round, .. }
+            | BrakeErr { round, .. }
+            | InvalidProposer { round, .. }
+            | CheckBlockErr { round, .. }
+            | MultiProposal { round, .. }
+            | SaveWalErr { round, .. }
+            | CryptoErr { round, .. }
+            | InvalidSignature { round, .. }
+            | AggregatedSignatureErr { round, .. } => Some(*round),
+            _ => None,
+        }
+    }
+
+    /// Get the offending address which signs the message.
+    pub fn address(&self) -> Option<&Address> {
+        use self::ConsensusError::*;
+        match self {
+            InvalidAddress(address)
+            | InvalidProposer { address, .. }
+            | MultiProposal { address, .. } => Some(address),
+            InvalidSignature { address, .. } => address.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl Error for ConsensusError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        use self::ConsensusError::*;
+        match self {
+            CheckBlockErr { source, .. }
+            | ApplicationErr { source, .. }
+            | SaveWalErr { source, .. }
+            | CryptoErr { source, .. }
+            | InvalidSignature { source, .. }
+            | WalErr(source) => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 #[cfg(test)]
 impl PartialEq for ConsensusError {
@@ -92,13 +367,13 @@ impl PartialEq for ConsensusError {
         match (self, other) {
             // If compare objects are the following types of error, as long as the error type need
             // the same, the details are ignored.
-            (InvalidAddress, InvalidAddress)
+            (InvalidAddress(_), InvalidAddress(_))
             | (TriggerSMRErr(_), TriggerSMRErr(_))
             | (MonitorEventErr(_), MonitorEventErr(_))
             | (ThrowEventErr(_), ThrowEventErr(_))
-            | (ProposalErr(_), ProposalErr(_))
-            | (PrevoteErr(_), PrevoteErr(_))
-            | (PrecommitErr(_), PrecommitErr(_))
+            | (ProposalErr { .. }, ProposalErr { .. })
+            | (PrevoteErr { .. }, PrevoteErr { .. })
+            | (PrecommitErr { .. }, PrecommitErr { .. })
             | (SelfCheckErr(_), SelfCheckErr(_)) => true,
             // If it is the following two types of errors, in the judgment, the error type need the
             // same, and the error information need the same.
@@ -111,3 +386,61 @@ impl PartialEq for ConsensusError {
 
 #[cfg(test)]
 impl Eq for ConsensusError {}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+    use std::fmt;
+
+    use bytes::Bytes;
+
+    use super::{ConsensusError, ErrorKind};
+
+    #[test]
+    fn test_error_kind() {
+        let address = Bytes::from(vec![1u8]);
+        let err = ConsensusError::InvalidSignature {
+            height:  10,
+            round:   1,
+            address: Some(address.clone()),
+            source:  Box::new(fmt::Error),
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidSignature);
+        assert!(err.kind().is_peer_fault());
+        assert_eq!((err.height(), err.round()), (Some(10), Some(1)));
+        assert_eq!(err.address(), Some(&address));
+        assert!(err.source().unwrap().downcast_ref::<fmt::Error>().is_some());
+
+        let err = ConsensusError::SaveWalErr {
+            height: 10,
+            round:  1,
+            step:   "Propose".to_string(),
+            source: Box::new(ConsensusError::Other("disk full".to_string())),
+        };
+        assert_eq!(err.kind(), ErrorKind::Storage);
+        assert!(!err.kind().is_peer_fault());
+        assert_eq!(err.address(), None);
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            "Other error disk full".to_string()
+        );
+
+        let err = ConsensusError::InvalidAddress(address.clone());
+        assert_eq!(err.kind(), ErrorKind::UnknownVoter);
+        assert_eq!(err.address(), Some(&address));
+        assert_eq!(err.height(), None);
+
+        let err = ConsensusError::AggregatedSignatureErr {
+            height: 10,
+            round:  1,
+            reason: "not above threshold".to_string(),
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidSignature);
+        assert_eq!((err.height(), err.round()), (Some(10), Some(1)));
+        assert_eq!(err.address(), None);
+
+        let err = ConsensusError::ChannelErr("closed".to_string());
+        assert_eq!(err.kind(), ErrorKind::Internal);
+        assert!(err.source().is_none());
+    }
+}
@@ -9,14 +9,20 @@
 
 /// A module that impl rlp encodable and decodable trait for types that need to save wal.
 mod codec;
+/// Built-in proposer election strategies.
+pub mod election;
 /// Overlord error module.
 pub mod error;
 /// Create and run the overlord consensus process.
 pub mod overlord;
+/// Built-in clock and spawner of the overlord runtime.
+pub mod runtime;
+/// serialize codec content in hex format
+mod serde_codec;
 /// serialize Bytes in hex format
 pub mod serde_hex;
-/// serialize Vec<Bytes> in hex format
-mod serde_multi_hex;
+/// A deterministic simulator to run overlord instances with virtual time.
+pub mod sim;
 /// State machine replicas module to do state changes.
 mod smr;
 /// The state module to storage proposals and votes.
@@ -29,22 +35,34 @@ pub mod types;
 mod utils;
 /// Write ahead log module.
 mod wal;
+/// The wire format of the messages between the nodes.
+mod wire;
 
 pub use self::overlord::Overlord;
 pub use self::overlord::OverlordHandler;
-pub use self::utils::auth_manage::extract_voters;
+pub use self::runtime::BLOCKING_POOL_SIZE;
+pub use self::smr::smr_types::{Lock, Step};
+pub use self::utils::auth_manage::{extract_voters, verify_proof};
+pub use self::utils::domain::{signing_payload, MsgTag};
+pub use self::utils::event_hub::EVENT_BUFFER_SIZE;
+pub use self::utils::peer_guard::{get_peer, with_peer};
 pub use creep::Context;
-pub use wal::WalInfo;
+pub use wal::{FileWal, WalInfo, WalLock, Watermark, WAL_VERSION};
+pub use wire::WIRE_VERSION;
 
 use std::error::Error;
 use std::fmt::Debug;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 
 use crate::error::ConsensusError;
-use crate::types::{Address, Commit, Hash, Node, OverlordMsg, Signature, Status};
+use crate::types::{
+    Address, Commit, Evidence, Hash, Node, OverlordMsg, PeerPenalty, Signature, Status,
+};
 
 /// Overlord consensus result.
 pub type ConsensusResult<T> = ::std::result::Result<T, ConsensusError>;
@@ -60,7 +78,7 @@ pub trait Consensus<T: Codec>: Send + Sync {
         &self,
         ctx: Context,
         height: u64,
-    ) -> Result<(T, Hash), Box<dyn Error + Send>>;
+    ) -> Result<(T, Hash), Box<dyn Error + Send + Sync>>;
 
     /// Check the correctness of a block. If is passed, return the integrated transcations to do
     /// data persistence.
@@ -70,7 +88,7 @@ pub trait Consensus<T: Codec>: Send + Sync {
         height: u64,
         hash: Hash,
         block: T,
-    ) -> Result<(), Box<dyn Error + Send>>;
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
 
     /// Commit a given height to execute and return the rich status.
     async fn commit(
@@ -78,21 +96,21 @@ pub trait Consensus<T: Codec>: Send + Sync {
         ctx: Context,
         height: u64,
         commit: Commit<T>,
-    ) -> Result<Status, Box<dyn Error + Send>>;
+    ) -> Result<Status, Box<dyn Error + Send + Sync>>;
 
     /// Get an authority list of the given height.
     async fn get_authority_list(
         &self,
         ctx: Context,
         height: u64,
-    ) -> Result<Vec<Node>, Box<dyn Error + Send>>;
+    ) -> Result<Vec<Node>, Box<dyn Error + Send + Sync>>;
 
     /// Broadcast a message to other replicas.
     async fn broadcast_to_other(
         &self,
         ctx: Context,
         msg: OverlordMsg<T>,
-    ) -> Result<(), Box<dyn Error + Send>>;
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
 
     /// Transmit a message to the Relayer, the third argument is the relayer's address.
     async fn transmit_to_relayer(
@@ -100,29 +118,41 @@ pub trait Consensus<T: Codec>: Send + Sync {
         ctx: Context,
         addr: Address,
         msg: OverlordMsg<T>,
-    ) -> Result<(), Box<dyn Error + Send>>;
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
 
-    /// Report the overlord error with the corresponding context.
+    /// Report the overlord error with the corresponding context. The error tells its kind by
+    /// `ConsensusError::kind()`, such as an invalid signature from a peer or a failure of the wal,
+    /// and carries the height, the round, the offending address and the source error if any.
     fn report_error(&self, ctx: Context, error: ConsensusError);
+
+    /// Report an evidence of equivocation with the corresponding context. The signatures in the
+    /// evidence have been verified, so it can be used to punish the byzantine validator. The
+    /// default implementation ignores the evidence.
+    fn report_evidence(&self, _ctx: Context, _evidence: Evidence<T>) {}
+
+    /// Report a penalty of a peer which sends excessive or invalid messages, so that the network
+    /// layer can disconnect the abuser. The peer is got from the context by `get_peer()`. The
+    /// default implementation ignores the penalty.
+    fn report_penalty(&self, _ctx: Context, _penalty: PeerPenalty) {}
 }
 
 /// Trait for doing serialize and deserialize.
 pub trait Codec: Clone + Debug + Send + PartialEq + Eq {
     /// Serialize self into bytes.
-    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>>;
+    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>>;
 
     /// Deserialize date into self.
-    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>>;
+    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>>;
 }
 
 /// Trait for save and load wal information.
 #[async_trait]
 pub trait Wal {
     /// Save wal information.
-    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send>>;
+    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send + Sync>>;
 
     /// Load wal information.
-    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send>>;
+    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send + Sync>>;
 }
 
 /// Trait for some crypto methods.
@@ -131,14 +161,14 @@ pub trait Crypto: Send {
     fn hash(&self, msg: Bytes) -> Hash;
 
     /// Sign to the given hash by private key and return the signature if success.
-    fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send>>;
+    fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send + Sync>>;
 
     /// Aggregate the given signatures into an aggregated signature according to the given bitmap.
     fn aggregate_signatures(
         &self,
         signatures: Vec<Signature>,
         voters: Vec<Address>,
-    ) -> Result<Signature, Box<dyn Error + Send>>;
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>>;
 
     /// Verify a signature and return the recovered address.
     fn verify_signature(
@@ -146,7 +176,7 @@ pub trait Crypto: Send {
         signature: Signature,
         hash: Hash,
         voter: Address,
-    ) -> Result<(), Box<dyn Error + Send>>;
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
 
     /// Verify an aggregated signature.
     fn verify_aggregated_signature(
@@ -154,7 +184,75 @@ pub trait Crypto: Send {
         aggregate_signature: Signature,
         msg_hash: Hash,
         voters: Vec<Address>,
-    ) -> Result<(), Box<dyn Error + Send>>;
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Verify a batch of signatures, each with its hash and voter, and return the results in the
+    /// same order. The signed votes are verified in batches off the state loop. The default
+    /// implementation verifies the signatures one by one, override it if the signature scheme
+    /// supports batch verification.
+    fn batch_verify(
+        &self,
+        signatures: Vec<(Signature, Hash, Address)>,
+    ) -> Vec<Result<(), Box<dyn Error + Send + Sync>>> {
+        signatures
+            .into_iter()
+            .map(|(signature, hash, voter)| self.verify_signature(signature, hash, voter))
+            .collect()
+    }
+}
+
+/// Trait for signing the messages of overlord asynchronously, so that the signing can await an
+/// external signer, such as a remote KMS or an HSM. Every `Crypto` signs locally as a signer, set
+/// another signer by `Overlord::set_signer()`.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// Sign to the given hash and return the signature if success.
+    async fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send + Sync>>;
+}
+
+#[async_trait]
+impl<C: Crypto + Sync> Signer for C {
+    async fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        Crypto::sign(self, hash)
+    }
+}
+
+/// Trait for electing the proposer of each height and round. Every node must use the same election
+/// strategy, since signed proposals from others are verified by it.
+pub trait ProposerElection: Debug + Send + Sync {
+    /// Elect the proposer of the given height and round from the authority list, which is sorted by
+    /// address.
+    fn elect(&self, height: u64, round: u64, authority_list: &[Node]) -> ConsensusResult<Address>;
+
+    /// Called after a block is committed with the height and the block hash, and when a status
+    /// carries the block hash of the previous height. Strategies that are seeded by the previous
+    /// block can record the seed here.
+    fn on_commit(&self, _height: u64, _block_hash: &Hash) {}
+}
+
+/// Trait for the time source of overlord. All the timeouts and the height interval are measured by
+/// the clock, so a virtual clock can be injected to run overlord deterministically.
+pub trait Clock: Debug + Send + Sync {
+    /// Get the current instant.
+    fn now(&self) -> Instant;
+
+    /// Create a future that completes after the given duration.
+    fn delay(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// Trait for spawning the background tasks of overlord.
+pub trait Spawner: Debug + Send + Sync {
+    /// Spawn a future to run in the background.
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+
+    /// Run a blocking or CPU-bound task, such as the disk I/O of the wal and the batch
+    /// verification of votes, off the threads of the async executor. The default implementation
+    /// runs the task in a pool of `BLOCKING_POOL_SIZE` threads shared by all the spawners, which is
+    /// created on the first use. The tasks queue up when all the threads are busy, so a spawner
+    /// with a larger pool should override it.
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        runtime::spawn_in_pool(task);
+    }
 }
 
 /// The setting of the timeout interval of each step.
@@ -214,6 +312,43 @@ impl DurationConfig {
     }
 }
 
+/// The rate limit of the messages from each peer, which works as a token bucket refilled at
+/// `rate` tokens per second up to `burst` tokens.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The number of messages allowed per second.
+    pub rate: u32,
+    /// The maximum number of messages allowed in a burst.
+    pub burst: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            rate:  500,
+            burst: 1000,
+        }
+    }
+}
+
+/// The size limits of the messages decoded from the wire.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WireLimit {
+    /// The max size in bytes of a signed proposal, which contains the block content.
+    pub max_proposal_size: usize,
+    /// The max size in bytes of the other messages.
+    pub max_msg_size: usize,
+}
+
+impl Default for WireLimit {
+    fn default() -> Self {
+        WireLimit {
+            max_proposal_size: 16 * 1024 * 1024,
+            max_msg_size:      1024 * 1024,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::DurationConfig;
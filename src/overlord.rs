@@ -1,26 +1,51 @@
+use std::error::Error;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use creep::Context;
-use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use derive_more::Display;
+use futures::channel::mpsc::{unbounded, Receiver, UnboundedReceiver, UnboundedSender};
+use futures::channel::oneshot;
+use futures::{future, pin_mut, select_biased, FutureExt};
 use parking_lot::RwLock;
 
 use crate::error::ConsensusError;
+use crate::runtime::SystemClock;
 use crate::state::process::State;
-use crate::types::{Address, Node, OverlordMsg};
-use crate::DurationConfig;
+use crate::types::{Address, ConsensusEvent, Node, OverlordMsg, Snapshot};
+use crate::utils::event_hub::EventHub;
+use crate::utils::msg_queue::MsgQueue;
+use crate::utils::peer_guard::PeerGuard;
 use crate::{smr::SMR, timer::Timer};
-use crate::{Codec, Consensus, ConsensusResult, Crypto, Wal};
+use crate::{
+    Clock, Codec, Consensus, ConsensusResult, Crypto, ProposerElection, Signer, Spawner, Wal,
+};
+use crate::{DurationConfig, RateLimit};
+
+/// The default capacity of the inbound message queue.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
 
 type Pile<T> = RwLock<Option<T>>;
+type Binding<T> = Arc<RwLock<UnboundedSender<T>>>;
+pub(crate) type StatusQuery = oneshot::Sender<Snapshot>;
 
-/// An overlord consensus instance.
+/// An overlord consensus instance. The instance can run again after a run exits, with fresh
+/// channels bound to the instance and all its handlers.
 pub struct Overlord<T: Codec, F: Consensus<T>, C: Crypto, W: Wal> {
-    sender:    Pile<UnboundedSender<(Context, OverlordMsg<T>)>>,
-    state_rx:  Pile<UnboundedReceiver<(Context, OverlordMsg<T>)>>,
-    address:   Pile<Address>,
-    consensus: Pile<Arc<F>>,
-    crypto:    Pile<Arc<C>>,
-    wal:       Pile<Arc<W>>,
+    queue:     MsgQueue<T>,
+    peers:     PeerGuard,
+    query_tx:  Binding<StatusQuery>,
+    query_rx:  Pile<UnboundedReceiver<StatusQuery>>,
+    events:    EventHub,
+    address:   RwLock<Address>,
+    chain_id:  RwLock<Bytes>,
+    consensus: RwLock<Arc<F>>,
+    crypto:    RwLock<Arc<C>>,
+    signer:    RwLock<Option<Arc<dyn Signer>>>,
+    wal:       RwLock<Arc<W>>,
+    election:  RwLock<Arc<dyn ProposerElection>>,
+    clock:     RwLock<Arc<dyn Clock>>,
+    spawner:   RwLock<Arc<dyn Spawner>>,
 }
 
 impl<T, F, C, W> Overlord<T, F, C, W>
@@ -30,100 +55,323 @@ where
     C: Crypto + Send + Sync + 'static,
     W: Wal + 'static,
 {
-    /// Create a new overlord and return an overlord instance with an unbounded receiver.
-    pub fn new(address: Address, consensus: Arc<F>, crypto: Arc<C>, wal: Arc<W>) -> Self {
-        let (tx, rx) = unbounded();
+    /// Create a new overlord and return an overlord instance with an unbounded receiver. The
+    /// `election` decides the proposer of each height and round, see the `election` module for
+    /// the built-in strategies. The `spawner` runs the background tasks of overlord, use
+    /// `runtime::TokioSpawner` with the `tokio-runtime` feature or implement `Spawner` for any
+    /// other executor.
+    pub fn new(
+        address: Address,
+        consensus: Arc<F>,
+        crypto: Arc<C>,
+        wal: Arc<W>,
+        election: Arc<dyn ProposerElection>,
+        spawner: Arc<dyn Spawner>,
+    ) -> Self {
+        Overlord::with_runtime(
+            address,
+            consensus,
+            crypto,
+            wal,
+            election,
+            Arc::new(SystemClock),
+            spawner,
+        )
+    }
+
+    /// Create a new overlord with the given clock and spawner as the runtime, others are the same
+    /// as `new()`. It is used to run overlord by a custom executor or in the simulator.
+    pub fn with_runtime(
+        address: Address,
+        consensus: Arc<F>,
+        crypto: Arc<C>,
+        wal: Arc<W>,
+        election: Arc<dyn ProposerElection>,
+        clock: Arc<dyn Clock>,
+        spawner: Arc<dyn Spawner>,
+    ) -> Self {
+        let (query_tx, query_rx) = unbounded();
         Overlord {
-            sender:    RwLock::new(Some(tx)),
-            state_rx:  RwLock::new(Some(rx)),
-            address:   RwLock::new(Some(address)),
-            consensus: RwLock::new(Some(consensus)),
-            crypto:    RwLock::new(Some(crypto)),
-            wal:       RwLock::new(Some(wal)),
+            queue:     MsgQueue::new(DEFAULT_QUEUE_CAPACITY),
+            peers:     PeerGuard::new(Arc::clone(&clock), Some(RateLimit::default())),
+            query_tx:  Arc::new(RwLock::new(query_tx)),
+            query_rx:  RwLock::new(Some(query_rx)),
+            events:    EventHub::new(),
+            address:   RwLock::new(address),
+            chain_id:  RwLock::new(Bytes::new()),
+            consensus: RwLock::new(consensus),
+            crypto:    RwLock::new(crypto),
+            signer:    RwLock::new(None),
+            wal:       RwLock::new(wal),
+            election:  RwLock::new(election),
+            clock:     RwLock::new(clock),
+            spawner:   RwLock::new(spawner),
         }
     }
 
-    /// Get the overlord handler from the overlord instance.
+    /// Get the overlord handler from the overlord instance. The handler keeps working after the
+    /// instance restarts.
     pub fn get_handler(&self) -> OverlordHandler<T> {
-        let sender = self.sender.write();
-        assert!(sender.is_some());
-        let tx = sender.clone().unwrap();
-        OverlordHandler::new(tx)
+        OverlordHandler::new(
+            self.queue.clone(),
+            self.peers.clone(),
+            Arc::clone(&self.query_tx),
+            self.events.clone(),
+        )
+    }
+
+    /// Subscribe consensus events of the overlord instance. The returned receiver can be dropped
+    /// to unsubscribe. A subscriber which lags behind more than `EVENT_BUFFER_SIZE` events is
+    /// unsubscribed, and its stream ends.
+    pub fn subscribe(&self) -> Receiver<ConsensusEvent> {
+        self.events.subscribe()
+    }
+
+    /// Set the capacity of the inbound message queue, which is `DEFAULT_QUEUE_CAPACITY` by
+    /// default.
+    pub fn set_queue_capacity(&self, capacity: usize) {
+        self.queue.set_capacity(capacity);
+    }
+
+    /// Set the rate limit of the messages from each peer, which is `RateLimit::default()` by
+    /// default. `None` means no rate limit.
+    pub fn set_rate_limit(&self, limit: Option<RateLimit>) {
+        self.peers.set_rate_limit(limit);
+    }
+
+    /// Set the address of the instance, which takes effect from the next run.
+    pub fn set_address(&self, address: Address) {
+        *self.address.write() = address;
+    }
+
+    /// Set the chain id of the instance, which takes effect from the next run. The chain id is
+    /// mixed into every signed message, so all the nodes of a chain must set the same chain id.
+    /// It is empty by default.
+    pub fn set_chain_id(&self, chain_id: Bytes) {
+        *self.chain_id.write() = chain_id;
+    }
+
+    /// Set the crypto of the instance, which takes effect from the next run. It is used with
+    /// `set_address()` to rotate the key.
+    pub fn set_crypto(&self, crypto: Arc<C>) {
+        *self.crypto.write() = crypto;
     }
 
-    /// Run overlord consensus process. The `interval` is the height interval as millisecond.
+    /// Set the signer of the instance, which takes effect from the next run. The crypto signs by
+    /// default, set a signer to sign by an external signer, such as a remote KMS or an HSM.
+    pub fn set_signer(&self, signer: Arc<dyn Signer>) {
+        *self.signer.write() = Some(signer);
+    }
+
+    /// Run overlord consensus process. The `interval` is the height interval as millisecond. It
+    /// resolves after all the internal tasks exit on `OverlordMsg::Stop`, or returns the error
+    /// when the SMR or timer fails fatally. After a run exits, the instance can run again, and
+    /// the messages sent in between are handled by the next run. Return an error if the instance
+    /// is already running.
     pub async fn run(
         &self,
         interval: u64,
         authority_list: Vec<Node>,
         timer_config: Option<DurationConfig>,
     ) -> ConsensusResult<()> {
+        let query_rx =
+            self.query_rx.write().take().ok_or_else(|| {
+                ConsensusError::StateErr("Overlord is already running".to_string())
+            })?;
+        // Bind fresh channels when the run exits, even if it is canceled.
+        let _guard = RebindGuard(self);
+
+        let clock = Arc::clone(&*self.clock.read());
+        let spawner = Arc::clone(&*self.spawner.read());
         let (mut smr_provider, evt_state, evt_timer) = SMR::new();
         let smr_handler = smr_provider.take_smr();
-        let timer = Timer::new(evt_timer, smr_handler.clone(), interval, timer_config);
-
-        let (rx, mut state, resp) = {
-            let mut state_rx = self.state_rx.write();
-            let mut address = self.address.write();
-            let mut consensus = self.consensus.write();
-            let mut crypto = self.crypto.write();
-            let mut wal = self.wal.write();
-            // let sender = self.sender.read();
-
-            let tmp_rx = state_rx.take().unwrap();
-            let (tmp_state, tmp_resp) = State::new(
-                smr_handler,
-                address.take().unwrap(),
-                interval,
-                authority_list,
-                consensus.take().unwrap(),
-                crypto.take().unwrap(),
-                wal.take().unwrap(),
-            );
-
-            // assert!(sender.is_none());
-            assert!(address.is_none());
-            assert!(consensus.is_none());
-            assert!(crypto.is_none());
-            assert!(state_rx.is_none());
-            assert!(wal.is_none());
-
-            (tmp_rx, tmp_state, tmp_resp)
-        };
+        let timer = Timer::new(
+            evt_timer,
+            smr_handler.clone(),
+            interval,
+            timer_config,
+            Arc::clone(&clock),
+            Arc::clone(&spawner),
+        );
 
-        log::info!("Overlord start running");
+        let crypto = Arc::clone(&*self.crypto.read());
+        let signer = self
+            .signer
+            .read()
+            .clone()
+            .unwrap_or_else(|| Arc::clone(&crypto) as Arc<dyn Signer>);
+        let (mut state, resp, verified_votes) = State::new(
+            smr_handler,
+            self.address.read().clone(),
+            self.chain_id.read().clone(),
+            interval,
+            authority_list,
+            Arc::clone(&*self.consensus.read()),
+            crypto,
+            signer,
+            Arc::clone(&*self.wal.read()),
+            Arc::clone(&*self.election.read()),
+            self.events.clone(),
+            self.peers.clone(),
+            Arc::clone(&clock),
+            Arc::clone(&spawner),
+        );
 
-        // Run SMR.
-        smr_provider.run();
+        log::info!("Overlord start running");
 
-        // Run timer.
-        timer.run();
+        // Run SMR and timer.
+        let smr_task = smr_provider.run(spawner.as_ref()).map(join_result);
+        let timer_task = timer.run().map(join_result);
+        let tasks = future::try_join(smr_task, timer_task).fuse();
 
         // Run state.
-        state.run(rx, evt_state, resp).await;
+        let state_task = state
+            .run(
+                self.queue.clone(),
+                evt_state,
+                resp,
+                verified_votes,
+                query_rx,
+            )
+            .fuse();
+
+        // Return the fatal error of SMR or timer. Otherwise, wait for all the tasks to exit on
+        // stop.
+        pin_mut!(tasks, state_task);
+        select_biased! {
+            res = tasks => {
+                res?;
+                state_task.await;
+            }
+            _ = state_task => {
+                tasks.await?;
+            }
+        }
 
         Ok(())
     }
 }
 
-/// An overlord handler to send messages to an overlord instance.
+impl<T: Codec, F: Consensus<T>, C: Crypto, W: Wal> Overlord<T, F, C, W> {
+    /// Bind fresh channels to the instance and all its handlers.
+    fn rebind(&self) {
+        let (query_tx, query_rx) = unbounded();
+        self.queue.clear();
+        *self.query_rx.write() = Some(query_rx);
+        *self.query_tx.write() = query_tx;
+    }
+}
+
+impl<T: Codec, F: Consensus<T>, C: Crypto, W: Wal> Drop for Overlord<T, F, C, W> {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+struct RebindGuard<'a, T: Codec, F: Consensus<T>, C: Crypto, W: Wal>(&'a Overlord<T, F, C, W>);
+
+impl<'a, T: Codec, F: Consensus<T>, C: Crypto, W: Wal> Drop for RebindGuard<'a, T, F, C, W> {
+    fn drop(&mut self) {
+        self.0.rebind();
+    }
+}
+
+/// The error of `OverlordHandler::try_send`, which returns the message back.
+#[derive(Debug, Display)]
+pub enum TrySendError<T: Codec> {
+    /// The message queue is full.
+    #[display(fmt = "Message queue is full")]
+    Full(OverlordMsg<T>),
+    /// The overlord instance is dropped.
+    #[display(fmt = "Message queue is closed")]
+    Closed(OverlordMsg<T>),
+}
+
+impl<T: Codec> Error for TrySendError<T> {}
+
+fn join_result(res: Option<ConsensusResult<()>>) -> ConsensusResult<()> {
+    res.unwrap_or(Ok(()))
+}
+
+/// An overlord handler to send messages to an overlord instance and query its status.
 #[derive(Clone, Debug)]
-pub struct OverlordHandler<T: Codec>(UnboundedSender<(Context, OverlordMsg<T>)>);
+pub struct OverlordHandler<T: Codec> {
+    queue:    MsgQueue<T>,
+    peers:    PeerGuard,
+    query_tx: Binding<StatusQuery>,
+    events:   EventHub,
+}
 
 impl<T: Codec> OverlordHandler<T> {
-    fn new(tx: UnboundedSender<(Context, OverlordMsg<T>)>) -> Self {
-        OverlordHandler(tx)
+    fn new(
+        queue: MsgQueue<T>,
+        peers: PeerGuard,
+        query_tx: Binding<StatusQuery>,
+        events: EventHub,
+    ) -> Self {
+        OverlordHandler {
+            queue,
+            peers,
+            query_tx,
+            events,
+        }
     }
 
-    /// Send overlord message to the instance. Return `Err()` when the message channel is closed.
+    /// Send overlord message to the instance. Return `Err(ConsensusError::ChannelErr)` when the
+    /// message queue is full or the overlord instance is dropped. Control messages, `RichStatus`
+    /// and `Stop`, are never rejected as full. See `try_send()` for the details.
     pub fn send_msg(&self, ctx: Context, msg: OverlordMsg<T>) -> ConsensusResult<()> {
-        if self.0.is_closed() {
-            log::error!("[OverlordHandler]: channel closed");
-            Ok(())
-        } else {
-            self.0
-                .unbounded_send((ctx, msg))
-                .map_err(|e| ConsensusError::Other(format!("Send message error {:?}", e)))
+        self.try_send(ctx, msg).map_err(|err| match err {
+            TrySendError::Full(msg) => {
+                ConsensusError::ChannelErr(format!("Message queue is full, drop {}", msg))
+            }
+            TrySendError::Closed(msg) => {
+                ConsensusError::ChannelErr(format!("Message queue is closed, drop {}", msg))
+            }
+        })
+    }
+
+    /// Try to send overlord message to the instance. When the message queue is full, the message
+    /// evicts the oldest queued message of a lower priority, or it is returned in
+    /// `TrySendError::Full`. Control messages have the highest priority and are never rejected as
+    /// full, then QCs, proposals, and individual votes. A message of a height lower than the
+    /// current height is dropped directly.
+    ///
+    /// If the context carries the peer who sends the message by `with_peer()`, the message is
+    /// dropped when the peer exceeds the rate limit, and the misbehaviours of the peer are reported
+    /// by `Consensus::report_penalty()`.
+    pub fn try_send(&self, ctx: Context, msg: OverlordMsg<T>) -> Result<(), TrySendError<T>> {
+        if !self
+            .peers
+            .admit(&ctx, msg.get_height(), self.queue.height())
+        {
+            return Ok(());
         }
+        self.queue.try_push(ctx, msg)
+    }
+
+    /// Forget the rate limit and the penalties of a peer, for example, when the peer is
+    /// disconnected.
+    pub fn forget_peer(&self, peer: &Address) {
+        self.peers.forget(peer);
+    }
+
+    /// Query a snapshot of the consensus state, including the height, round, leader and the step
+    /// and lock of the state machine. The query is answered once the overlord instance is running.
+    pub async fn status(&self) -> ConsensusResult<Snapshot> {
+        let (tx, rx) = oneshot::channel();
+        self.query_tx
+            .read()
+            .unbounded_send(tx)
+            .map_err(|e| ConsensusError::ChannelErr(format!("Send status query error {:?}", e)))?;
+        rx.await
+            .map_err(|_| ConsensusError::ChannelErr("Status query canceled".to_string()))
+    }
+
+    /// Subscribe consensus events. The returned receiver will receive all the events published
+    /// after subscription, and it can be dropped to unsubscribe. A subscriber which lags behind
+    /// more than `EVENT_BUFFER_SIZE` events is unsubscribed, and its stream ends.
+    pub fn subscribe(&self) -> Receiver<ConsensusEvent> {
+        self.events.subscribe()
     }
 }
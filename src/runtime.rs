@@ -0,0 +1,152 @@
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::channel::oneshot;
+use futures::future::{abortable, AbortHandle, Aborted, BoxFuture, FutureExt, RemoteHandle};
+use futures::task::noop_waker_ref;
+use futures_timer::Delay;
+use lazy_static::lazy_static;
+use log::error;
+use parking_lot::Mutex;
+
+use crate::{Clock, Spawner};
+
+/// The system clock which measures the time by `Instant::now()` and delays by a futures timer.
+#[derive(Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn delay(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Delay::new(duration).boxed()
+    }
+}
+
+/// The spawner which spawns tasks into the tokio runtime.
+#[cfg(feature = "tokio-runtime")]
+#[derive(Clone, Debug, Default)]
+pub struct TokioSpawner;
+
+#[cfg(feature = "tokio-runtime")]
+impl Spawner for TokioSpawner {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        tokio::spawn(future);
+    }
+
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        tokio::task::spawn_blocking(task);
+    }
+}
+
+/// The number of threads in the pool of the default `Spawner::spawn_blocking()`.
+pub const BLOCKING_POOL_SIZE: usize = 4;
+
+type BlockingTask = Box<dyn FnOnce() + Send>;
+
+lazy_static! {
+    static ref BLOCKING_POOL: Mutex<Sender<BlockingTask>> = Mutex::new(start_blocking_pool());
+}
+
+fn start_blocking_pool() -> Sender<BlockingTask> {
+    let (tx, rx) = mpsc::channel::<BlockingTask>();
+    let rx = Arc::new(Mutex::new(rx));
+    for index in 0..BLOCKING_POOL_SIZE {
+        let rx = Arc::clone(&rx);
+        thread::Builder::new()
+            .name(format!("overlord-blocking-{}", index))
+            .spawn(move || run_blocking_worker(&rx))
+            .expect("spawn blocking pool thread");
+    }
+    tx
+}
+
+fn run_blocking_worker(rx: &Mutex<Receiver<BlockingTask>>) {
+    loop {
+        let task = match rx.lock().recv() {
+            Ok(task) => task,
+            Err(_) => return,
+        };
+        // A panic of the task drops its output sender, keep the thread alive for other tasks.
+        if panic::catch_unwind(AssertUnwindSafe(task)).is_err() {
+            error!("Overlord: a blocking task panics");
+        }
+    }
+}
+
+/// Run the task in the shared blocking pool.
+pub(crate) fn spawn_in_pool(task: BlockingTask) {
+    let _ = BLOCKING_POOL.lock().send(task);
+}
+
+/// Spawn the future by the spawner and return a handle to join or abort the task.
+pub(crate) fn spawn_with_handle<F>(spawner: &dyn Spawner, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let (future, abort) = abortable(future);
+    let (remote, handle) = future.remote_handle();
+    spawner.spawn(remote.boxed());
+    JoinHandle { handle, abort }
+}
+
+/// Run the blocking task by the spawner and return a future which resolves to the output of the
+/// task, or `None` if the task panics or is dropped by the spawner.
+pub(crate) fn run_blocking<F, R>(spawner: &dyn Spawner, task: F) -> impl Future<Output = Option<R>>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    spawner.spawn_blocking(Box::new(move || {
+        let _ = tx.send(task());
+    }));
+    rx.map(Result::ok)
+}
+
+/// A handle of a spawned task, which resolves to the output of the task or `None` if the task is
+/// aborted. Dropping the handle cancels the task.
+#[must_use = "dropping the handle cancels the task"]
+pub(crate) struct JoinHandle<T> {
+    handle: RemoteHandle<Result<T, Aborted>>,
+    abort:  AbortHandle,
+}
+
+impl<T> Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("JoinHandle").finish()
+    }
+}
+
+impl<T: 'static> Future for JoinHandle<T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.handle.poll_unpin(cx).map(Result::ok)
+    }
+}
+
+impl<T> JoinHandle<T> {
+    /// Abort the task. The task exits the next time it is polled, and the handle resolves to
+    /// `None`.
+    pub(crate) fn abort(&self) {
+        self.abort.abort();
+    }
+
+    /// Check whether the task has finished, or has been aborted, without waiting for it. The output
+    /// of a finished task is dropped.
+    pub(crate) fn is_finished(&mut self) -> bool {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        self.handle.poll_unpin(&mut cx).is_ready()
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use bytes::Bytes;
+use serde::{de, Deserializer, Serializer};
+
+use crate::Codec;
+
+/// serialize Codec content with hex of its encoded bytes
+pub fn serialize<S, T>(val: &T, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Codec,
+{
+    let bytes = val
+        .encode()
+        .map_err(|e| serde::ser::Error::custom(format!("{:?}", e)))?;
+    s.serialize_str(&hex::encode(bytes))
+}
+
+struct CodecVisit<T>(PhantomData<T>);
+
+/// deserialize Codec content from hex of its encoded bytes
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Codec,
+{
+    deserializer.deserialize_str(CodecVisit(PhantomData))
+}
+
+impl<'de, T: Codec> de::Visitor<'de> for CodecVisit<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("hex of encoded content")
+    }
+
+    #[inline]
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let value = hex::decode(v).map_err(de::Error::custom)?;
+        T::decode(Bytes::from(value)).map_err(|e| de::Error::custom(format!("{:?}", e)))
+    }
+}
@@ -37,3 +37,30 @@ impl<'de> de::Visitor<'de> for StringVisit {
         Ok(Bytes::from(value))
     }
 }
+
+/// Serialize and deserialize optional Bytes with hex.
+pub mod option {
+    use bytes::Bytes;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    /// serialize optional Bytes with hex
+    pub fn serialize<S>(val: &Option<Bytes>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match val {
+            Some(val) => s.serialize_some(&hex::encode(val)),
+            None => s.serialize_none(),
+        }
+    }
+
+    /// deserialize optional Bytes with hex
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Bytes>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|v| hex::decode(v).map(Bytes::from).map_err(de::Error::custom))
+            .transpose()
+    }
+}
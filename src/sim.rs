@@ -0,0 +1,442 @@
+use std::cmp::max;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{Context as TaskContext, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use creep::Context;
+use futures::future::{BoxFuture, FutureExt};
+use futures::task::{waker, ArcWake};
+use parking_lot::Mutex;
+use rand_core::{RngCore, SeedableRng};
+use rand_pcg::Pcg64Mcg as Pcg;
+
+use crate::overlord::OverlordHandler;
+use crate::types::{Address, OverlordMsg};
+use crate::utils::peer_guard::with_peer;
+use crate::{Clock, Codec, Spawner, WireLimit};
+
+/// A deterministic single-threaded executor with a virtual clock. It implements both `Clock`
+/// and `Spawner`, so overlord instances built by `Overlord::with_runtime` run on it.
+///
+/// The simulator polls one ready task at a time, and the task is picked by a random number
+/// generator of the given seed. The virtual time only goes forward when no task is ready, and
+/// then it jumps to the earliest timer. Therefore, a run is reproduced bit-for-bit by the same
+/// seed, and a timeout of seconds costs no wall-clock time.
+#[derive(Clone, Debug)]
+pub struct Simulator {
+    executor: Arc<Mutex<Executor>>,
+}
+
+impl Simulator {
+    /// Create a simulator with the seed of task scheduling and message latency.
+    pub fn new(seed: u64) -> Self {
+        Simulator {
+            executor: Arc::new(Mutex::new(Executor::new(seed))),
+        }
+    }
+
+    /// Get the virtual clock of the simulator.
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        Arc::new(self.clone())
+    }
+
+    /// Get the spawner of the simulator.
+    pub fn spawner(&self) -> Arc<dyn Spawner> {
+        Arc::new(self.clone())
+    }
+
+    /// Get the virtual time elapsed since the simulator was created.
+    pub fn elapsed(&self) -> Duration {
+        self.executor.lock().elapsed
+    }
+
+    /// Get the number of the spawned tasks that have not finished.
+    pub fn task_count(&self) -> usize {
+        self.executor.lock().tasks.len()
+    }
+
+    /// Generate a random number from the seeded random number generator.
+    pub fn random(&self) -> u64 {
+        self.executor.lock().rng.next_u64()
+    }
+
+    /// Run the tasks until the virtual time goes `duration` forward.
+    pub fn run_for(&self, duration: Duration) {
+        let deadline = self.elapsed() + duration;
+        while self.step(deadline) {}
+    }
+
+    /// Run the tasks until `cond` returns true or the virtual time goes `timeout` forward. Return
+    /// whether `cond` is satisfied.
+    pub fn run_until<F: FnMut() -> bool>(&self, timeout: Duration, mut cond: F) -> bool {
+        let deadline = self.elapsed() + timeout;
+        loop {
+            if cond() {
+                return true;
+            }
+            if !self.step(deadline) {
+                return cond();
+            }
+        }
+    }
+
+    /// Poll a ready task, or fire the earliest timers if no task is ready. Return false if there
+    /// is nothing to do before the deadline.
+    fn step(&self, deadline: Duration) -> bool {
+        let (id, mut task) = {
+            let mut executor = self.executor.lock();
+            if executor.ready.is_empty() {
+                let expired = executor.fire_timers(deadline);
+                drop(executor);
+                if expired.is_empty() {
+                    return false;
+                }
+                expired.into_iter().for_each(Waker::wake);
+                return true;
+            }
+
+            let index = (executor.rng.next_u64() % executor.ready.len() as u64) as usize;
+            let id = *executor.ready.iter().nth(index).unwrap();
+            executor.ready.remove(&id);
+            match executor.tasks.get_mut(&id).and_then(Option::take) {
+                Some(task) => (id, task),
+                None => return true,
+            }
+        };
+
+        let task_waker = waker(Arc::new(TaskWaker {
+            id,
+            executor: Arc::downgrade(&self.executor),
+        }));
+        let mut cx = TaskContext::from_waker(&task_waker);
+        if task.as_mut().poll(&mut cx).is_pending() {
+            if let Some(slot) = self.executor.lock().tasks.get_mut(&id) {
+                *slot = Some(task);
+            }
+        } else {
+            self.executor.lock().tasks.remove(&id);
+        }
+        true
+    }
+}
+
+impl Clock for Simulator {
+    fn now(&self) -> Instant {
+        let executor = self.executor.lock();
+        executor.start + executor.elapsed
+    }
+
+    fn delay(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        SimDelay {
+            deadline: self.elapsed() + duration,
+            key:      None,
+            executor: Arc::downgrade(&self.executor),
+        }
+        .boxed()
+    }
+}
+
+impl Spawner for Simulator {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        let mut executor = self.executor.lock();
+        executor.task_id += 1;
+        let id = executor.task_id;
+        executor.tasks.insert(id, Some(future));
+        executor.ready.insert(id);
+    }
+
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        // Run the task in the executor to keep the simulation deterministic.
+        self.spawn(async move { task() }.boxed());
+    }
+}
+
+/// A simulated network which delivers overlord messages between the registered overlord
+/// instances with a random latency from the simulator. The messages are delivered in the wire
+/// format, the messages which can not be put on the wire are dropped.
+#[derive(Clone, Debug)]
+pub struct SimNetwork<T: Codec> {
+    simulator:   Simulator,
+    handlers:    Arc<Mutex<BTreeMap<Address, OverlordHandler<T>>>>,
+    min_latency: Duration,
+    max_latency: Duration,
+}
+
+impl<T: Codec + 'static> SimNetwork<T> {
+    /// Create a simulated network whose latency is between `min_latency` and `max_latency`.
+    pub fn new(simulator: &Simulator, min_latency: Duration, max_latency: Duration) -> Self {
+        assert!(min_latency <= max_latency);
+        SimNetwork {
+            simulator: simulator.clone(),
+            handlers: Arc::new(Mutex::new(BTreeMap::new())),
+            min_latency,
+            max_latency,
+        }
+    }
+
+    /// Register the handler of an overlord instance in the network.
+    pub fn register(&self, address: Address, handler: OverlordHandler<T>) {
+        self.handlers.lock().insert(address, handler);
+    }
+
+    /// Remove an overlord instance from the network, which simulates a crashed or partitioned
+    /// node.
+    pub fn remove(&self, address: &Address) {
+        self.handlers.lock().remove(address);
+    }
+
+    /// Broadcast the message to all the other registered overlord instances. The messages carry
+    /// the sender as the peer in the context.
+    pub fn broadcast(&self, from: &Address, msg: OverlordMsg<T>) {
+        let data = match msg.to_wire_bytes() {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        let handlers = self
+            .handlers
+            .lock()
+            .iter()
+            .filter(|(address, _)| *address != from)
+            .map(|(_, handler)| handler.clone())
+            .collect::<Vec<_>>();
+        for handler in handlers {
+            self.deliver(from, handler, data.clone());
+        }
+    }
+
+    /// Transmit the message from the sender to the given overlord instance.
+    pub fn transmit(&self, from: &Address, to: &Address, msg: OverlordMsg<T>) {
+        let handler = self.handlers.lock().get(to).cloned();
+        if let (Some(handler), Ok(data)) = (handler, msg.to_wire_bytes()) {
+            self.deliver(from, handler, data);
+        }
+    }
+
+    fn deliver(&self, from: &Address, handler: OverlordHandler<T>, data: Bytes) {
+        let ctx = with_peer(&Context::new(), from.clone());
+        let span = (self.max_latency - self.min_latency).as_micros() as u64;
+        let latency =
+            self.min_latency + Duration::from_micros(self.simulator.random() % (span + 1));
+        let delay = self.simulator.delay(latency);
+        self.simulator.spawn(
+            async move {
+                delay.await;
+                if let Ok(msg) = OverlordMsg::from_wire_bytes(&data, WireLimit::default()) {
+                    let _ = handler.send_msg(ctx, msg);
+                }
+            }
+            .boxed(),
+        );
+    }
+}
+
+struct Executor {
+    start:    Instant,
+    elapsed:  Duration,
+    rng:      Pcg,
+    task_id:  u64,
+    tasks:    BTreeMap<u64, Option<BoxFuture<'static, ()>>>,
+    ready:    BTreeSet<u64>,
+    timer_id: u64,
+    timers:   BTreeMap<(Duration, u64), Waker>,
+}
+
+impl Debug for Executor {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("Executor")
+            .field("elapsed", &self.elapsed)
+            .field("tasks", &self.tasks.len())
+            .field("ready", &self.ready.len())
+            .field("timers", &self.timers.len())
+            .finish()
+    }
+}
+
+impl Executor {
+    fn new(seed: u64) -> Self {
+        Executor {
+            start:    Instant::now(),
+            elapsed:  Duration::from_secs(0),
+            rng:      Pcg::seed_from_u64(seed),
+            task_id:  0,
+            tasks:    BTreeMap::new(),
+            ready:    BTreeSet::new(),
+            timer_id: 0,
+            timers:   BTreeMap::new(),
+        }
+    }
+
+    /// Advance the virtual time to the earliest timer and take the wakers of all the expired
+    /// timers. If there is no timer before the deadline, advance the virtual time to the deadline.
+    fn fire_timers(&mut self, deadline: Duration) -> Vec<Waker> {
+        match self.timers.keys().next() {
+            Some((when, _)) if *when <= deadline => self.elapsed = max(self.elapsed, *when),
+            _ => {
+                self.elapsed = max(self.elapsed, deadline);
+                return Vec::new();
+            }
+        }
+
+        let pending = self.timers.split_off(&(self.elapsed, u64::max_value()));
+        mem::replace(&mut self.timers, pending)
+            .into_iter()
+            .map(|(_, waker)| waker)
+            .collect()
+    }
+}
+
+struct TaskWaker {
+    id:       u64,
+    executor: Weak<Mutex<Executor>>,
+}
+
+impl ArcWake for TaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        if let Some(executor) = arc_self.executor.upgrade() {
+            executor.lock().ready.insert(arc_self.id);
+        }
+    }
+}
+
+struct SimDelay {
+    deadline: Duration,
+    key:      Option<(Duration, u64)>,
+    executor: Weak<Mutex<Executor>>,
+}
+
+impl Future for SimDelay {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let executor = match this.executor.upgrade() {
+            Some(executor) => executor,
+            None => return Poll::Pending,
+        };
+        let mut executor = executor.lock();
+
+        if executor.elapsed >= this.deadline {
+            if let Some(key) = this.key.take() {
+                executor.timers.remove(&key);
+            }
+            return Poll::Ready(());
+        }
+
+        let key = match this.key {
+            Some(key) => key,
+            None => {
+                executor.timer_id += 1;
+                (this.deadline, executor.timer_id)
+            }
+        };
+        executor.timers.insert(key, cx.waker().clone());
+        this.key = Some(key);
+        Poll::Pending
+    }
+}
+
+impl Drop for SimDelay {
+    fn drop(&mut self) {
+        if let (Some(key), Some(executor)) = (self.key.take(), self.executor.upgrade()) {
+            executor.lock().timers.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use futures::channel::mpsc::unbounded;
+    use futures::{FutureExt, StreamExt};
+    use parking_lot::Mutex;
+
+    use crate::{Clock, Spawner};
+
+    use super::Simulator;
+
+    fn spawn_sleepers(sim: &Simulator, record: &Arc<Mutex<Vec<u64>>>) {
+        for i in 0..10u64 {
+            let clock = sim.clock();
+            let record = Arc::clone(record);
+            sim.spawn(
+                async move {
+                    clock.delay(Duration::from_millis(100 - i)).await;
+                    record.lock().push(i);
+                }
+                .boxed(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_virtual_time() {
+        let sim = Simulator::new(0);
+        let record = Arc::new(Mutex::new(Vec::new()));
+        spawn_sleepers(&sim, &record);
+
+        let start = sim.now();
+        sim.run_for(Duration::from_millis(95));
+        assert_eq!(*record.lock(), vec![9, 8, 7, 6, 5]);
+        assert_eq!(sim.now() - start, Duration::from_millis(95));
+
+        sim.run_for(Duration::from_secs(3600));
+        assert_eq!(*record.lock(), vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 0]);
+        assert_eq!(sim.elapsed(), Duration::from_millis(3_600_095));
+    }
+
+    #[test]
+    fn test_run_until() {
+        let sim = Simulator::new(0);
+        let (tx, mut rx) = unbounded();
+        let record = Arc::new(Mutex::new(Vec::new()));
+        let clock = sim.clock();
+        sim.spawn(
+            async move {
+                for i in 0..3u64 {
+                    clock.delay(Duration::from_millis(10)).await;
+                    tx.unbounded_send(i).unwrap();
+                }
+            }
+            .boxed(),
+        );
+        let inner = Arc::clone(&record);
+        sim.spawn(
+            async move {
+                while let Some(i) = rx.next().await {
+                    inner.lock().push(i);
+                }
+            }
+            .boxed(),
+        );
+
+        assert!(sim.run_until(Duration::from_secs(1), || record.lock().len() == 2));
+        assert_eq!(sim.elapsed(), Duration::from_millis(20));
+        assert!(!sim.run_until(Duration::from_secs(1), || record.lock().len() == 4));
+        assert_eq!(*record.lock(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_seeded_schedule() {
+        let schedule = |seed: u64| {
+            let sim = Simulator::new(seed);
+            let record = Arc::new(Mutex::new(Vec::new()));
+            for i in 0..20u64 {
+                let record = Arc::clone(&record);
+                sim.spawn(async move { record.lock().push(i) }.boxed());
+            }
+            sim.run_for(Duration::from_secs(1));
+            Arc::try_unwrap(record).unwrap().into_inner()
+        };
+
+        assert_eq!(schedule(7), schedule(7));
+        assert_ne!(schedule(7), schedule(8));
+    }
+}
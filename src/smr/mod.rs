@@ -10,10 +10,11 @@ use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use futures::stream::{FusedStream, Stream, StreamExt};
 use log::error;
 
+use crate::runtime::{spawn_with_handle, JoinHandle};
 use crate::smr::smr_types::{SMREvent, SMRStatus, SMRTrigger, TriggerSource, TriggerType};
 use crate::smr::state_machine::StateMachine;
 use crate::types::Hash;
-use crate::{error::ConsensusError, ConsensusResult, INIT_ROUND};
+use crate::{error::ConsensusError, ConsensusResult, Spawner, INIT_ROUND};
 
 ///
 #[derive(Debug)]
@@ -42,18 +43,23 @@ impl SMR {
         self.smr_handler.take().unwrap()
     }
 
-    /// Run SMR module in tokio environment.
-    pub fn run(mut self) {
-        tokio::spawn(async move {
-            loop {
-                let res = self.state_machine.next().await;
-                if let Some(Err(err)) = res {
-                    error!("Overlord: SMR error {:?}", err);
-                } else if res.is_none() {
-                    break;
+    /// Run SMR module by the spawner and return the join handle. The SMR exits with `Ok(())` on
+    /// the stop trigger, or with an error if the trigger or event channel is broken.
+    pub fn run(mut self, spawner: &dyn Spawner) -> JoinHandle<ConsensusResult<()>> {
+        spawn_with_handle(spawner, async move {
+            while let Some(res) = self.state_machine.next().await {
+                match res {
+                    Err(err @ ConsensusError::TriggerSMRErr(_))
+                    | Err(err @ ConsensusError::ThrowEventErr(_)) => {
+                        error!("Overlord: SMR fatal error {:?}", err);
+                        return Err(err);
+                    }
+                    Err(err) => error!("Overlord: SMR error {:?}", err),
+                    Ok(_) => (),
                 }
             }
-        });
+            Ok(())
+        })
     }
 }
 
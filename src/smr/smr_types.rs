@@ -1,4 +1,7 @@
+use std::convert::TryFrom;
+
 use derive_more::Display;
+use rlp::DecoderError;
 use serde::{Deserialize, Serialize};
 
 use crate::types::Hash;
@@ -74,15 +77,17 @@ impl Into<u8> for Step {
     }
 }
 
-impl From<u8> for Step {
-    fn from(s: u8) -> Self {
+impl TryFrom<u8> for Step {
+    type Error = DecoderError;
+
+    fn try_from(s: u8) -> Result<Self, Self::Error> {
         match s {
-            0 => Step::Propose,
-            1 => Step::Prevote,
-            2 => Step::Precommit,
-            3 => Step::Brake,
-            4 => Step::Commit,
-            _ => panic!("Invalid vote type!"),
+            0 => Ok(Step::Propose),
+            1 => Ok(Step::Prevote),
+            2 => Ok(Step::Precommit),
+            3 => Ok(Step::Brake),
+            4 => Ok(Step::Commit),
+            _ => Err(DecoderError::Custom("Invalid step")),
         }
     }
 }
@@ -207,6 +212,9 @@ pub enum TriggerType {
     /// Continue new round trigger.
     #[display(fmt = "Continue Round")]
     ContinueRound,
+    /// The proposal block is rejected by the check.
+    #[display(fmt = "Block Rejected")]
+    BlockRejected,
     /// Stop process.
     #[display(fmt = "Stop Process")]
     Stop,
@@ -235,15 +243,16 @@ impl Into<u8> for TriggerType {
     }
 }
 
-impl From<u8> for TriggerType {
-    /// It should not occur that call `from(3u8)`.
-    fn from(s: u8) -> Self {
+impl TryFrom<u8> for TriggerType {
+    type Error = DecoderError;
+
+    /// Only the proposal, prevote QC and precommit QC triggers can be converted from `u8`.
+    fn try_from(s: u8) -> Result<Self, Self::Error> {
         match s {
-            0 => TriggerType::Proposal,
-            1 => TriggerType::PrevoteQC,
-            2 => TriggerType::PrecommitQC,
-            3 => unreachable!(),
-            _ => panic!("Invalid trigger type!"),
+            0 => Ok(TriggerType::Proposal),
+            1 => Ok(TriggerType::PrevoteQC),
+            2 => Ok(TriggerType::PrecommitQC),
+            _ => Err(DecoderError::Custom("Invalid trigger type")),
         }
     }
 }
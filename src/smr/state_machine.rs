@@ -70,6 +70,10 @@ impl Stream for StateMachine {
                         assert!(msg.source == TriggerSource::State);
                         Some(self.handle_continue_round(msg.height, msg.round))
                     }
+                    TriggerType::BlockRejected => {
+                        assert!(msg.source == TriggerSource::State);
+                        Some(self.handle_block_rejected(msg.hash, msg.height, msg.round))
+                    }
                     TriggerType::WalInfo => Some(self.handle_wal(msg.wal_info.unwrap())),
                     TriggerType::Stop => {
                         let _ = self.throw_event(SMREvent::Stop);
@@ -143,6 +147,33 @@ impl StateMachine {
         Ok(())
     }
 
+    /// Handle a block rejected trigger. If the rejected block is the one that self votes for in
+    /// the current round, there is no need to wait for the timeout. In prevote step, goto precommit
+    /// step as the prevote timeout. After that, the block hash is cleared, so the trigger is
+    /// ignored in the later steps.
+    fn handle_block_rejected(
+        &mut self,
+        hash: Hash,
+        height: u64,
+        round: u64,
+    ) -> ConsensusResult<()> {
+        if height != self.height || round != self.round || hash != self.block_hash {
+            return Ok(());
+        }
+
+        info!(
+            "Overlord: SMR triggered by block rejected hash {:?}, height {}, round {}",
+            hex::encode(hash.clone()),
+            self.height,
+            self.round
+        );
+
+        if self.step == Step::Prevote {
+            return self.handle_prevote(hash, round, TriggerSource::Timer, height);
+        }
+        Ok(())
+    }
+
     fn handle_wal(&mut self, info: SMRBase) -> ConsensusResult<()> {
         self.height = info.height;
         self.round = info.round;
@@ -231,7 +262,11 @@ impl StateMachine {
             self.goto_step(Step::Prevote);
             return Ok(());
         } else if proposal_hash.is_empty() {
-            return Err(ConsensusError::ProposalErr("Empty proposal".to_string()));
+            return Err(ConsensusError::ProposalErr {
+                height: self.height,
+                round:  self.round,
+                reason: "Empty proposal".to_string(),
+            });
         }
 
         // update PoLC
@@ -590,8 +625,50 @@ impl StateMachine {
 #[cfg(test)]
 mod test {
     use bytes::Bytes;
+    use futures::channel::mpsc::unbounded;
+    use futures::StreamExt;
     use std::ops::BitXor;
 
+    use crate::smr::smr_types::{SMREvent, Step};
+
+    use super::StateMachine;
+
+    #[test]
+    fn test_block_rejected() {
+        let (_tx, rx) = unbounded();
+        let (mut state_machine, mut evt_state, _evt_timer) = StateMachine::new(rx);
+        let hash = Bytes::from(vec![1u8, 2, 3]);
+        state_machine.height = 1;
+        state_machine.step = Step::Prevote;
+        state_machine.block_hash = hash.clone();
+
+        // Rejection of another block is ignored.
+        state_machine
+            .handle_block_rejected(Bytes::from(vec![4u8]), 1, 0)
+            .unwrap();
+        assert_eq!(state_machine.step, Step::Prevote);
+
+        // Precommit nil in prevote step.
+        state_machine
+            .handle_block_rejected(hash.clone(), 1, 0)
+            .unwrap();
+        assert_eq!(state_machine.step, Step::Precommit);
+        assert_eq!(
+            futures::executor::block_on(evt_state.next()),
+            Some(SMREvent::PrecommitVote {
+                height:     1,
+                round:      0,
+                block_hash: Bytes::new(),
+                lock_round: None,
+            })
+        );
+
+        // The block hash is cleared, so a repeated rejection is ignored in precommit step.
+        assert!(state_machine.block_hash.is_empty());
+        state_machine.handle_block_rejected(hash, 1, 0).unwrap();
+        assert_eq!(state_machine.step, Step::Precommit);
+    }
+
     #[test]
     fn test_xor() {
         let left = Bytes::new();
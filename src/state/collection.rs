@@ -3,9 +3,10 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use creep::Context;
 
 use crate::types::{
-    Address, AggregatedChoke, AggregatedVote, Hash, SignedChoke, SignedProposal, SignedVote,
-    VoteType,
+    Address, AggregatedChoke, AggregatedVote, Evidence, Hash, SignedChoke, SignedProposal,
+    SignedVote, VoteType,
 };
+use crate::utils::auth_manage::AuthorityManage;
 use crate::{error::ConsensusError, Codec, ConsensusResult};
 
 /// A struct to collect signed proposals in each height. It stores each height and the corresponding
@@ -31,11 +32,16 @@ where
         round: u64,
         proposal: SignedProposal<T>,
     ) -> ConsensusResult<()> {
+        let address = proposal.proposal.proposer.clone();
         self.0
             .entry(height)
             .or_insert_with(ProposalRoundCollector::new)
             .insert(ctx, round, proposal)
-            .map_err(|_| ConsensusError::MultiProposal(height, round))
+            .map_err(|_| ConsensusError::MultiProposal {
+                height,
+                round,
+                address,
+            })
     }
 
     /// Get the signed proposal of the given height and round. Return `Err` when there is no
@@ -59,6 +65,27 @@ where
         )))
     }
 
+    /// Check whether the given signed proposal conflicts with the one of the same height and round
+    /// in the collector. Return an evidence if both proposals are proposed by the same proposer
+    /// but differ. The signatures are not verified here.
+    pub fn check_conflict(
+        &self,
+        height: u64,
+        round: u64,
+        signed_proposal: &SignedProposal<T>,
+    ) -> Option<Evidence<T>> {
+        let (exist, _) = self.0.get(&height)?.get(round).ok()?;
+        if exist.proposal.proposer == signed_proposal.proposal.proposer
+            && exist.proposal != signed_proposal.proposal
+        {
+            return Some(Evidence::DoubleProposal(
+                exist.to_owned(),
+                signed_proposal.to_owned(),
+            ));
+        }
+        None
+    }
+
     /// Get all proposals of the given height.
     pub fn get_height_proposals(
         &mut self,
@@ -131,6 +158,29 @@ impl VoteCollector {
             .insert_vote(hash, vote, addr);
     }
 
+    /// Check whether the given signed vote conflicts with the vote of the same voter, height, round
+    /// and vote type in the collector. Return an evidence if the two votes are for different
+    /// block hashes. Each equivocation is returned only once, so a resent conflicting vote will not
+    /// be reported again.
+    pub fn check_conflict<T: Codec>(&mut self, signed_vote: &SignedVote) -> Option<Evidence<T>> {
+        let round = signed_vote.get_round();
+        let vote_type = signed_vote.vote.vote_type.clone();
+        let vrc = self.0.get_mut(&signed_vote.get_height())?;
+        let exist = vrc.get_vote(round, vote_type.clone(), &signed_vote.voter)?;
+        if exist.vote.block_hash == signed_vote.vote.block_hash {
+            return None;
+        }
+
+        let evidence = Evidence::DoubleVote(exist.to_owned(), signed_vote.to_owned());
+        if vrc
+            .reported
+            .insert((round, vote_type, signed_vote.voter.clone()))
+        {
+            return Some(evidence);
+        }
+        None
+    }
+
     /// Set a given quorum certificate to the collector.
     pub fn set_qc(&mut self, qc: AggregatedVote) {
         self.0
@@ -246,6 +296,7 @@ impl VoteCollector {
 struct VoteRoundCollector {
     general:    HashMap<u64, RoundCollector>,
     qc_by_hash: HashMap<Hash, QuorumCertificate>,
+    reported:   HashSet<(u64, VoteType, Address)>,
 }
 
 impl VoteRoundCollector {
@@ -253,6 +304,7 @@ impl VoteRoundCollector {
         VoteRoundCollector {
             general:    HashMap::new(),
             qc_by_hash: HashMap::new(),
+            reported:   HashSet::new(),
         }
     }
 
@@ -300,6 +352,12 @@ impl VoteRoundCollector {
             .and_then(|rc| rc.get_votes(vote_type, hash))
     }
 
+    fn get_vote(&self, round: u64, vote_type: VoteType, addr: &Address) -> Option<&SignedVote> {
+        self.general
+            .get(&round)
+            .and_then(|rc| rc.get_vote(vote_type, addr))
+    }
+
     fn get_qc_by_id(&mut self, round: u64, qc_type: VoteType) -> Option<AggregatedVote> {
         self.general
             .get_mut(&round)
@@ -364,6 +422,13 @@ impl RoundCollector {
         }
     }
 
+    fn get_vote(&self, vote_type: VoteType, addr: &Address) -> Option<&SignedVote> {
+        match vote_type {
+            VoteType::Prevote => self.prevote.get_vote(addr),
+            VoteType::Precommit => self.precommit.get_vote(addr),
+        }
+    }
+
     fn get_qc(&mut self, qc_type: VoteType) -> Option<AggregatedVote> {
         self.qc.get_quorum_certificate(qc_type)
     }
@@ -468,6 +533,10 @@ impl Votes {
         })
     }
 
+    fn get_vote(&self, addr: &Address) -> Option<&SignedVote> {
+        self.by_address.get(addr)
+    }
+
     fn get_all_votes(&mut self) -> Vec<SignedVote> {
         self.by_address.values().cloned().collect::<Vec<_>>()
     }
@@ -512,9 +581,20 @@ impl ChokeCollector {
         self.qcs.get(&round).cloned()
     }
 
-    pub fn max_round_above_threshold(&self, nodes_num: usize) -> Option<u64> {
+    /// Get the max round that the sum of the vote weights of the chokes is above 2/3. Each voter is
+    /// counted only once in a round.
+    pub fn max_round_above_threshold(&self, authority: &AuthorityManage) -> Option<u64> {
+        let threshold = authority.get_vote_weight_sum() * 2;
         for (round, set) in self.chokes.iter().rev() {
-            if set.len() * 3 > nodes_num * 2 {
+            let acc = set
+                .iter()
+                .map(|sc| &sc.address)
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .filter_map(|addr| authority.get_vote_weight(addr).ok())
+                .map(|weight| u64::from(*weight))
+                .sum::<u64>();
+            if acc * 3 > threshold {
                 return Some(*round);
             }
         }
@@ -559,12 +639,15 @@ mod test {
     use serde::{Deserialize, Serialize};
     use test::Bencher;
 
-    use crate::state::collection::{ProposalCollector, VoteCollector};
+    use crate::state::collection::{ChokeCollector, ProposalCollector, VoteCollector};
+    use crate::state::verify::test::{gen_pending_vote, BlakeCrypto};
+    use crate::state::verify::{verify_votes, VERIFY_BATCH_SIZE};
     use crate::types::{
-        Address, AggregatedSignature, AggregatedVote, Hash, Proposal, Signature, SignedProposal,
-        SignedVote, Vote, VoteType,
+        Address, AggregatedSignature, AggregatedVote, Choke, Evidence, Hash, Node, Proposal,
+        Signature, SignedChoke, SignedProposal, SignedVote, UpdateFrom, Vote, VoteType,
     };
-    use crate::Codec;
+    use crate::utils::auth_manage::AuthorityManage;
+    use crate::{Codec, Crypto};
 
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
     struct Pill {
@@ -573,12 +656,12 @@ mod test {
     }
 
     impl Codec for Pill {
-        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
             let encode: Vec<u8> = serialize(&self).expect("Serialize Pill error");
             Ok(Bytes::from(encode))
         }
 
-        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
             let decode: Pill = deserialize(&data.as_ref()).expect("Deserialize Pill error.");
             Ok(decode)
         }
@@ -752,6 +835,102 @@ mod test {
         assert_eq!(res, vec.iter().cloned().collect::<HashSet<_>>());
     }
 
+    #[test]
+    fn test_proposal_conflict() {
+        let mut proposals = ProposalCollector::<Pill>::new();
+        let proposal_01 = gen_signed_proposal(1, 0);
+        let mut proposal_02 = gen_signed_proposal(1, 0);
+        assert!(proposals.check_conflict(1, 0, &proposal_01).is_none());
+        proposals
+            .insert(Context::new(), 1, 0, proposal_01.clone())
+            .unwrap();
+        assert!(proposals.check_conflict(1, 0, &proposal_01).is_none());
+
+        // A proposal of the same round from another proposer is not an equivocation.
+        assert!(proposals.check_conflict(1, 0, &proposal_02).is_none());
+
+        proposal_02.proposal.proposer = proposal_01.proposal.proposer.clone();
+        assert_eq!(
+            proposals.check_conflict(1, 0, &proposal_02),
+            Some(Evidence::DoubleProposal(proposal_01, proposal_02.clone()))
+        );
+        assert!(proposals.check_conflict(1, 1, &proposal_02).is_none());
+    }
+
+    #[test]
+    fn test_vote_conflict() {
+        let mut votes = VoteCollector::new();
+        let hash_01 = gen_hash();
+        let hash_02 = gen_hash();
+        let addr = gen_address();
+        let signed_vote_01 =
+            gen_signed_vote(1, 0, VoteType::Prevote, hash_01.clone(), addr.clone());
+        let signed_vote_02 = gen_signed_vote(1, 0, VoteType::Prevote, hash_02, addr.clone());
+        let signed_vote_03 =
+            gen_signed_vote(1, 0, VoteType::Precommit, hash_01.clone(), addr.clone());
+
+        votes.insert_vote(hash_01, signed_vote_01.clone(), addr);
+        assert!(votes.check_conflict::<Pill>(&signed_vote_01).is_none());
+        assert!(votes.check_conflict::<Pill>(&signed_vote_03).is_none());
+        assert_eq!(
+            votes.check_conflict::<Pill>(&signed_vote_02),
+            Some(Evidence::DoubleVote(signed_vote_01, signed_vote_02.clone()))
+        );
+        assert!(votes.check_conflict::<Pill>(&signed_vote_02).is_none());
+    }
+
+    #[test]
+    fn test_choke_weight_threshold() {
+        let weights = vec![4u32, 1, 1, 1];
+        let mut authority_list = weights
+            .iter()
+            .map(|weight| {
+                let mut node = Node::new(gen_address());
+                node.set_vote_weight(*weight);
+                node
+            })
+            .collect::<Vec<_>>();
+        let mut authority = AuthorityManage::new();
+        authority.update(&mut authority_list);
+
+        let gen_choke = |round: u64, address: Address| SignedChoke {
+            signature: gen_signature(),
+            choke: Choke {
+                height: 1,
+                round,
+                from: UpdateFrom::PrevoteQC(gen_aggregated_vote(1, round, VoteType::Prevote)),
+            },
+            address,
+        };
+
+        // Three light nodes are not above the threshold even if they are more than 2/3 in number.
+        let mut chokes = ChokeCollector::new();
+        for node in authority_list.iter().filter(|node| node.vote_weight == 1) {
+            chokes.insert(0, gen_choke(0, node.address.clone()));
+        }
+        assert!(chokes.max_round_above_threshold(&authority).is_none());
+
+        // Repeated chokes of the same voter are counted once.
+        let heavy = authority_list
+            .iter()
+            .find(|node| node.vote_weight == 4)
+            .unwrap()
+            .address
+            .clone();
+        chokes.insert(1, gen_choke(1, heavy.clone()));
+        chokes.insert(1, gen_choke(1, heavy.clone()));
+        assert!(chokes.max_round_above_threshold(&authority).is_none());
+
+        chokes.insert(0, gen_choke(0, heavy));
+        assert_eq!(chokes.max_round_above_threshold(&authority), Some(0));
+
+        // Chokes from unknown voters are ignored.
+        for _ in 0..10 {
+            chokes.insert(2, gen_choke(2, gen_address()));
+        }
+        assert_eq!(chokes.max_round_above_threshold(&authority), Some(0));
+    }
+
     #[bench]
     fn bench_insert_proposal(b: &mut Bencher) {
         let mut proposals = ProposalCollector::<Pill>::new();
@@ -774,6 +953,26 @@ mod test {
         b.iter(|| votes.insert_vote(hash.clone(), sv.clone(), addr.clone()))
     }
 
+    #[bench]
+    fn bench_verify_vote(b: &mut Bencher) {
+        let vote = gen_pending_vote(true);
+        b.iter(|| {
+            BlakeCrypto.verify_signature(
+                vote.signed_vote.signature.clone(),
+                vote.hash.clone(),
+                vote.signed_vote.voter.clone(),
+            )
+        });
+    }
+
+    #[bench]
+    fn bench_verify_votes_batch(b: &mut Bencher) {
+        let votes = (0..VERIFY_BATCH_SIZE)
+            .map(|_| gen_pending_vote(true))
+            .collect::<Vec<_>>();
+        b.iter(|| verify_votes(&BlakeCrypto, votes.clone()));
+    }
+
     #[bench]
     fn bench_insert_qc(b: &mut Bencher) {
         let mut votes = VoteCollector::new();
@@ -2,3 +2,5 @@
 mod collection;
 ///
 pub mod process;
+///
+mod verify;
@@ -1,46 +1,51 @@
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem;
 use std::string::ToString;
 use std::time::{Duration, Instant};
 use std::{ops::BitXor, sync::Arc};
 
-use bit_vec::BitVec;
 use bytes::Bytes;
 use creep::Context;
-use derive_more::Display;
 use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
-use futures::{select, StreamExt};
-use futures_timer::Delay;
+use futures::future::join_all;
+use futures::{select_biased, StreamExt};
 use log::{debug, error, info, warn};
 use moodyblues_sdk::trace;
 use muta_apm::derive::tracing_span;
+use parking_lot::Mutex;
 use serde_json::json;
 
 use crate::error::ConsensusError;
-use crate::smr::smr_types::{FromWhere, SMREvent, SMRTrigger, Step, TriggerSource, TriggerType};
+use crate::overlord::StatusQuery;
+use crate::runtime::{run_blocking, spawn_with_handle, JoinHandle};
+use crate::smr::smr_types::{
+    FromWhere, Lock, SMREvent, SMRTrigger, Step, TriggerSource, TriggerType,
+};
 use crate::smr::{Event, SMRHandler};
 use crate::state::collection::{ChokeCollector, ProposalCollector, VoteCollector};
+use crate::state::verify::{verify_votes, PendingVote, VerifiedVotes, VERIFY_BATCH_SIZE};
 use crate::types::{
-    Address, AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Commit, Hash, Node,
-    OverlordMsg, PoLC, Proof, Proposal, Signature, SignedChoke, SignedProposal, SignedVote, Status,
-    UpdateFrom, VerifyResp, Vote, VoteType,
+    Address, AggregatedChoke, AggregatedSignature, AggregatedVote, Choke, Commit, ConsensusEvent,
+    Evidence, Hash, Node, OverlordMsg, PoLC, Proof, Proposal, Signature, SignedChoke,
+    SignedProposal, SignedVote, Snapshot, Status, UpdateFrom, VerifyResp, Vote, VoteType,
 };
 use crate::utils::auth_manage::AuthorityManage;
-use crate::wal::{WalInfo, WalLock};
-use crate::{Codec, Consensus, ConsensusResult, Crypto, Wal, INIT_HEIGHT, INIT_ROUND};
+use crate::utils::domain::{signing_payload, MsgTag};
+use crate::utils::event_hub::EventHub;
+use crate::utils::msg_queue::MsgQueue;
+use crate::utils::peer_guard::{get_peer, misbehavior_of, with_peer, PeerGuard};
+use crate::utils::verify_cache::{VerifyCache, VerifyKey};
+use crate::wal::{WalInfo, WalLock, Watermark};
+use crate::{
+    Clock, Codec, Consensus, ConsensusResult, Crypto, ProposerElection, Signer, Spawner, Wal,
+    INIT_HEIGHT, INIT_ROUND,
+};
 
 const FUTURE_HEIGHT_GAP: u64 = 5;
+const VERIFY_CACHE_CAPACITY: usize = 4096;
 const FUTURE_ROUND_GAP: u64 = 10;
 
-#[derive(Clone, Debug, Display, PartialEq, Eq)]
-enum MsgType {
-    #[display(fmt = "Signed Proposal message")]
-    SignedProposal,
-
-    #[display(fmt = "Signed Vote message")]
-    SignedVote,
-}
-
 /// Overlord state struct. It maintains the local state of the node, and monitor the SMR event. The
 /// `proposals` is used to cache the signed proposals that are with higher height or round. The
 /// `hash_with_block` field saves hash and its corresponding block with the current height and
@@ -50,12 +55,16 @@ enum MsgType {
 pub struct State<T: Codec, F: Consensus<T>, C: Crypto, W: Wal> {
     height:              u64,
     round:               u64,
+    step:                Step,
+    lock:                Option<Lock>,
     state_machine:       SMRHandler,
     address:             Address,
+    chain_id:            Bytes,
     proposals:           ProposalCollector<T>,
     votes:               VoteCollector,
     chokes:              ChokeCollector,
     authority:           AuthorityManage,
+    authority_cache:     BTreeMap<u64, Option<AuthorityManage>>,
     hash_with_block:     HashMap<Hash, T>,
     is_full_transcation: HashMap<Hash, bool>,
     is_leader:           bool,
@@ -65,68 +74,117 @@ pub struct State<T: Codec, F: Consensus<T>, C: Crypto, W: Wal> {
     block_interval:      u64,
     consensus_power:     bool,
     stopped:             bool,
+    check_tasks:         Vec<JoinHandle<()>>,
+    verified:            Mutex<VerifyCache>,
+    verifying:           HashSet<VerifyKey>,
+    pending_votes:       Vec<PendingVote>,
+    verify_tasks:        BTreeMap<u64, JoinHandle<()>>,
+    verify_id:           u64,
+    watermark:           Option<Watermark>,
+    last_signature:      Option<Signature>,
 
     resp_tx:  UnboundedSender<VerifyResp>,
+    votes_tx: UnboundedSender<VerifiedVotes>,
+    events:   EventHub,
+    peers:    PeerGuard,
+    election: Arc<dyn ProposerElection>,
+    clock:    Arc<dyn Clock>,
+    spawner:  Arc<dyn Spawner>,
     function: Arc<F>,
     wal:      Arc<W>,
     util:     Arc<C>,
+    signer:   Arc<dyn Signer>,
 }
 
 impl<T, F, C, W> State<T, F, C, W>
 where
     T: Codec + 'static,
     F: Consensus<T> + 'static,
-    C: Crypto,
+    C: Crypto + Sync + 'static,
     W: Wal,
 {
     /// Create a new state struct.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         smr: SMRHandler,
         addr: Address,
+        chain: Bytes,
         interval: u64,
         mut authority_list: Vec<Node>,
         consensus: Arc<F>,
         crypto: Arc<C>,
+        message_signer: Arc<dyn Signer>,
         wal_engine: Arc<W>,
-    ) -> (Self, UnboundedReceiver<VerifyResp>) {
+        proposer_election: Arc<dyn ProposerElection>,
+        event_hub: EventHub,
+        peer_guard: PeerGuard,
+        clock_source: Arc<dyn Clock>,
+        task_spawner: Arc<dyn Spawner>,
+    ) -> (
+        Self,
+        UnboundedReceiver<VerifyResp>,
+        UnboundedReceiver<VerifiedVotes>,
+    ) {
         let (tx, rx) = unbounded();
+        let (verified_tx, verified_rx) = unbounded();
         let mut auth = AuthorityManage::new();
         auth.update(&mut authority_list);
 
         let state = State {
             height:              INIT_HEIGHT,
             round:               INIT_ROUND,
+            step:                Step::default(),
+            lock:                None,
             state_machine:       smr,
             address:             addr,
+            chain_id:            chain,
             proposals:           ProposalCollector::new(),
             votes:               VoteCollector::new(),
             chokes:              ChokeCollector::new(),
             authority:           auth,
+            authority_cache:     BTreeMap::new(),
             hash_with_block:     HashMap::new(),
             is_full_transcation: HashMap::new(),
             is_leader:           false,
             leader_address:      Address::default(),
             update_from_where:   UpdateFrom::PrecommitQC(mock_init_qc()),
-            height_start:        Instant::now(),
+            height_start:        clock_source.now(),
             block_interval:      interval,
             consensus_power:     false,
             stopped:             false,
+            check_tasks:         Vec::new(),
+            verified:            Mutex::new(VerifyCache::new(VERIFY_CACHE_CAPACITY)),
+            verifying:           HashSet::new(),
+            pending_votes:       Vec::new(),
+            verify_tasks:        BTreeMap::new(),
+            verify_id:           0,
+            watermark:           None,
+            last_signature:      None,
 
             resp_tx:  tx,
+            votes_tx: verified_tx,
+            events:   event_hub,
+            peers:    peer_guard,
+            election: proposer_election,
+            clock:    clock_source,
+            spawner:  task_spawner,
             function: consensus,
             util:     crypto,
+            signer:   message_signer,
             wal:      wal_engine,
         };
 
-        (state, rx)
+        (state, rx, verified_rx)
     }
 
     /// Run state module.
     pub(crate) async fn run(
         &mut self,
-        mut raw_rx: UnboundedReceiver<(Context, OverlordMsg<T>)>,
+        mut raw_rx: MsgQueue<T>,
         mut event: Event,
         mut verify_resp: UnboundedReceiver<VerifyResp>,
+        mut verified_votes: UnboundedReceiver<VerifiedVotes>,
+        mut query_rx: UnboundedReceiver<StatusQuery>,
     ) {
         debug!("Overlord: state start running");
         if let Err(e) = self.start_with_wal().await {
@@ -134,14 +192,16 @@ where
         }
 
         loop {
-            select! {
-                raw = raw_rx.next() => {
-                    let (ctx, msg) = raw.expect("Overlord message handler dropped");
-                    if let Err(e) = self.handle_msg(ctx.clone(), msg).await {
-                        self.report_error(ctx, e.clone());
-                        error!("Overlord: state {:?} error", e);
-                    }
-                }
+            // Drop the queued messages of previous heights.
+            raw_rx.set_height(self.height);
+            self.report_penalties();
+            // Verify the pending votes when a batch is full or no more message is queued.
+            self.flush_votes(raw_rx.is_empty());
+
+            // Poll the branches in order rather than randomly, so that a simulation is reproduced
+            // by the same seed. The events of SMR go first to keep the consensus going under a
+            // flood of messages.
+            select_biased! {
                 evt = event.next() => {
                     if self.stopped {
                         break;
@@ -164,8 +224,33 @@ where
                         error!("Overlord: state {:?} error", e);
                     }
                 }
+                votes = verified_votes.next() => {
+                    if let Some(votes) = votes {
+                        self.handle_verified_votes(votes).await;
+                    }
+                }
+                raw = raw_rx.next() => {
+                    let (ctx, msg) = raw.expect("Overlord message handler dropped");
+                    if let Err(e) = self.handle_msg(ctx.clone(), msg).await {
+                        error!("Overlord: state {:?} error", e);
+                        self.report_error(ctx, e);
+                    }
+                }
+                query = query_rx.next() => {
+                    // The receiver of a status query may have been dropped, just ignore it.
+                    if let Some(tx) = query {
+                        let _ = tx.send(self.snapshot());
+                    }
+                }
             }
         }
+
+        // Abort the block checks and vote verifications, and wait for them to exit.
+        self.check_tasks.iter().for_each(JoinHandle::abort);
+        join_all(self.check_tasks.drain(..)).await;
+        self.verify_tasks.values().for_each(JoinHandle::abort);
+        let verify_tasks = mem::replace(&mut self.verify_tasks, BTreeMap::new());
+        join_all(verify_tasks.into_iter().map(|(_, handle)| handle)).await;
     }
 
     /// A function to handle message from the network. Public this in the crate to do unit tests.
@@ -182,6 +267,7 @@ where
         match raw {
             OverlordMsg::SignedProposal(sp) => {
                 if let Err(e) = self.handle_signed_proposal(ctx.clone(), sp).await {
+                    self.punish(&ctx, &e);
                     trace::error(
                         "handle_signed_proposal".to_string(),
                         Some(json!({
@@ -197,6 +283,7 @@ where
 
             OverlordMsg::AggregatedVote(av) => {
                 if let Err(e) = self.handle_aggregated_vote(ctx.clone(), av).await {
+                    self.punish(&ctx, &e);
                     trace::error(
                         "handle_aggregated_vote".to_string(),
                         Some(json!({
@@ -212,6 +299,7 @@ where
 
             OverlordMsg::SignedVote(sv) => {
                 if let Err(e) = self.handle_signed_vote(ctx.clone(), sv).await {
+                    self.punish(&ctx, &e);
                     trace::error(
                         "handle_signed_vote".to_string(),
                         Some(json!({
@@ -227,6 +315,7 @@ where
 
             OverlordMsg::SignedChoke(sc) => {
                 if let Err(e) = self.handle_signed_choke(ctx.clone(), sc).await {
+                    self.punish(&ctx, &e);
                     trace::error(
                         "handle_choke".to_string(),
                         Some(json!({
@@ -277,7 +366,11 @@ where
 
     /// A function to handle event from the SMR. Public this function in the crate to do unit tests.
     pub(crate) async fn handle_event(&mut self, event: Option<SMREvent>) -> ConsensusResult<()> {
-        match event.ok_or_else(|| ConsensusError::Other("Event sender dropped".to_string()))? {
+        let event =
+            event.ok_or_else(|| ConsensusError::Other("Event sender dropped".to_string()))?;
+        self.update_smr_status(&event);
+
+        match event {
             SMREvent::NewRoundInfo {
                 round,
                 lock_round,
@@ -388,6 +481,62 @@ where
         }
     }
 
+    /// Track the step and the lock of the state machine from the SMR event, which are only used to
+    /// answer status queries.
+    fn update_smr_status(&mut self, event: &SMREvent) {
+        let (step, lock_round, hash) = match event {
+            SMREvent::NewRoundInfo {
+                lock_round,
+                lock_proposal,
+                ..
+            } => (Step::Propose, *lock_round, lock_proposal.clone()),
+            SMREvent::PrevoteVote {
+                lock_round,
+                block_hash,
+                ..
+            } => (Step::Prevote, *lock_round, Some(block_hash.clone())),
+            SMREvent::PrecommitVote {
+                lock_round,
+                block_hash,
+                ..
+            } => (Step::Precommit, *lock_round, Some(block_hash.clone())),
+            SMREvent::Brake {
+                height, lock_round, ..
+            } => {
+                if *height != self.height {
+                    return;
+                }
+                (Step::Brake, *lock_round, None)
+            }
+            SMREvent::Commit(_) => {
+                self.step = Step::Commit;
+                self.lock = None;
+                return;
+            }
+            _ => return,
+        };
+
+        self.step = step;
+        self.lock = match (lock_round, hash) {
+            (None, _) => None,
+            (Some(round), Some(hash)) if !hash.is_empty() => Some(Lock { round, hash }),
+            (Some(round), _) => self.lock.take().filter(|lock| lock.round == round),
+        };
+    }
+
+    /// Take a snapshot of the current consensus state.
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            height:            self.height,
+            round:             self.round,
+            step:              self.step.clone(),
+            lock:              self.lock.clone(),
+            leader_address:    self.leader_address.clone(),
+            is_leader:         self.is_leader,
+            update_from_where: self.update_from_where.clone(),
+        }
+    }
+
     fn handle_resp(&mut self, msg: Option<VerifyResp>) -> ConsensusResult<()> {
         let resp = msg.ok_or_else(|| ConsensusError::Other("Event sender dropped".to_string()))?;
         if resp.height != self.height {
@@ -396,7 +545,8 @@ where
 
         let block_hash = resp.block_hash.clone();
         info!(
-            "Overlord: state receive a verify response true, height {}, round {}, hash {:?}",
+            "Overlord: state receive a verify response {}, height {}, round {}, hash {:?}",
+            resp.is_pass,
             resp.height,
             resp.round,
             hex::encode(block_hash.clone())
@@ -407,13 +557,39 @@ where
             Some(json!({
                 "height": self.height,
                 "hash": hex::encode(block_hash.clone()),
-                "is_pass": true,
+                "is_pass": resp.is_pass,
+                "reason": resp.reason.clone(),
             })),
         );
 
         self.is_full_transcation
             .insert(block_hash.clone(), resp.is_pass);
 
+        // If the block is rejected, trigger SMR to precommit nil or brake without waiting for the
+        // timeout.
+        if !resp.is_pass {
+            warn!(
+                "Overlord: state reject block height {}, round {}, hash {:?}, reason {:?}",
+                resp.height,
+                resp.round,
+                hex::encode(block_hash.clone()),
+                resp.reason
+            );
+
+            if resp.round == self.round {
+                self.state_machine.trigger(SMRTrigger {
+                    trigger_type: TriggerType::BlockRejected,
+                    source:       TriggerSource::State,
+                    hash:         block_hash,
+                    lock_round:   None,
+                    round:        resp.round,
+                    height:       resp.height,
+                    wal_info:     None,
+                })?;
+            }
+            return Ok(());
+        }
+
         if let Some(qc) =
             self.votes
                 .get_qc_by_hash(self.height, block_hash.clone(), VoteType::Precommit)
@@ -465,8 +641,23 @@ where
         }
 
         let new_height = status.height;
+        if let Some(prev_hash) = status.prev_hash.as_ref() {
+            self.election.on_commit(new_height - 1, prev_hash);
+        }
         self.height = new_height;
         self.round = INIT_ROUND;
+        // Keep the authority lists of future heights, and ask again for the ones failed before.
+        self.authority_cache = self
+            .authority_cache
+            .split_off(&(new_height + 1))
+            .into_iter()
+            .filter(|(_, authority)| authority.is_some())
+            .collect();
+        // The block checks of previous heights are useless, abort them.
+        self.check_tasks.iter().for_each(JoinHandle::abort);
+        self.check_tasks.clear();
+        self.events
+            .publish(ConsensusEvent::NewHeight { height: new_height });
 
         // Check the consensus power.
         self.consensus_power = status.is_consensus_node(&self.address);
@@ -483,7 +674,7 @@ where
         self.save_wal(Step::Propose, None).await?;
 
         // Update height and authority list.
-        self.height_start = Instant::now();
+        self.height_start = self.clock.now();
         let mut auth_list = status.authority_list.clone();
         self.authority.update(&mut auth_list);
 
@@ -528,20 +719,26 @@ where
 
         self.round = round;
         self.is_leader = false;
+        self.events.publish(ConsensusEvent::NewRound {
+            height: self.height,
+            round,
+        });
 
         if lock_round.is_some().bitxor(lock_proposal.is_some()) {
-            return Err(ConsensusError::ProposalErr(
-                "Lock round is inconsistent with lock proposal".to_string(),
-            ));
+            return Err(ConsensusError::ProposalErr {
+                height: self.height,
+                round,
+                reason: "Lock round is inconsistent with lock proposal".to_string(),
+            });
         }
 
         self.set_update_from(from_where)?;
-        self.save_wal_with_lock_round(Step::Propose, lock_round)
-            .await?;
 
         // If self is not proposer, check whether it has received current signed proposal before. If
-        // has, then handle it.
+        // has, then handle it. The proposer saves the wal when it signs the proposal.
         if !self.is_proposer()? {
+            self.save_wal_with_lock_round(Step::Propose, lock_round)
+                .await?;
             if let Ok((signed_proposal, ctx)) = self.proposals.get(self.height, self.round) {
                 return self.handle_signed_proposal(ctx, signed_proposal).await;
             }
@@ -568,20 +765,33 @@ where
                 .function
                 .get_block(ctx.clone(), self.height)
                 .await
-                .map_err(|err| ConsensusError::Other(format!("get block error {:?}", err)))?;
+                .map_err(|source| ConsensusError::ApplicationErr {
+                    method: "get_block",
+                    height: self.height,
+                    source,
+                })?;
             (new_block, new_hash, None)
         } else {
             let round = lock_round.clone().unwrap();
             let hash = lock_proposal.unwrap();
-            let block = self.hash_with_block.get(&hash).ok_or_else(|| {
-                ConsensusError::ProposalErr(format!("Lose whole block that hash is {:?}", hash))
-            })?;
+            let block =
+                self.hash_with_block
+                    .get(&hash)
+                    .ok_or_else(|| ConsensusError::ProposalErr {
+                        height: self.height,
+                        round:  self.round,
+                        reason: format!("Lose whole block that hash is {:?}", hash),
+                    })?;
 
             // Create PoLC by prevoteQC.
             let qc = self
                 .votes
                 .get_qc_by_id(self.height, round, VoteType::Prevote)
-                .map_err(|err| ConsensusError::ProposalErr(format!("{:?} when propose", err)))?;
+                .map_err(|err| ConsensusError::ProposalErr {
+                    height: self.height,
+                    round:  self.round,
+                    reason: format!("{:?} when propose", err),
+                })?;
             let polc = PoLC {
                 lock_round: round,
                 lock_votes: qc,
@@ -602,6 +812,7 @@ where
             proposer:   self.address.clone(),
         };
 
+        let signed_proposal = self.sign_proposal(proposal, lock_round).await?;
         info!(
             "Overlord: state broadcast a signed proposal height {}, round {}, hash {:?} and trigger SMR",
             self.height,
@@ -609,11 +820,16 @@ where
             hex::encode(hash.clone())
         );
 
-        self.broadcast(
-            Context::new(),
-            OverlordMsg::SignedProposal(self.sign_proposal(proposal)?),
-        )
-        .await;
+        self.broadcast(Context::new(), OverlordMsg::SignedProposal(signed_proposal))
+            .await;
+
+        self.events.publish(ConsensusEvent::ProposalReceived {
+            height: self.height,
+            round: self.round,
+            block_hash: hash.clone(),
+            proposer: self.address.clone(),
+            lock_round,
+        });
 
         self.state_machine.trigger(SMRTrigger {
             trigger_type: TriggerType::Proposal,
@@ -648,6 +864,26 @@ where
             hex::encode(signed_proposal.proposal.block_hash.clone())
         );
 
+        self.check_proposal_conflict(ctx.clone(), &signed_proposal);
+
+        // Verify the future height proposal by the authority list of that height before caching, if
+        // the list is available. The proposer is verified as the proposal is re-checked at its
+        // height, since the election may depend on the blocks in between.
+        if proposal_height > self.height
+            && !self.filter_message(proposal_height, proposal_round)
+            && self.fetch_authority(ctx.clone(), proposal_height).await
+        {
+            self.verify_address(proposal_height, &signed_proposal.proposal.proposer)?;
+            self.verify_signature(
+                ctx.clone(),
+                proposal_height,
+                proposal_round,
+                self.signing_hash(MsgTag::Proposal, &signed_proposal.proposal),
+                signed_proposal.signature.clone(),
+                &signed_proposal.proposal.proposer,
+            )?;
+        }
+
         if self.filter_signed_proposal(
             ctx.clone(),
             proposal_height,
@@ -672,10 +908,11 @@ where
         self.verify_proposer(proposal_height, proposal_round, &proposal.proposer)?;
         self.verify_signature(
             ctx.clone(),
-            self.util.hash(Bytes::from(rlp::encode(&proposal))),
+            proposal_height,
+            proposal_round,
+            self.signing_hash(MsgTag::Proposal, &proposal),
             signature,
             &proposal.proposer,
-            MsgType::SignedProposal,
         )?;
 
         // If the signed proposal is with a lock, check the lock round and the QC then trigger it to
@@ -687,10 +924,14 @@ where
                 .authority
                 .is_above_threshold(&polc.lock_votes.signature.address_bitmap)?
             {
-                return Err(ConsensusError::AggregatedSignatureErr(format!(
-                    "aggregate signature below two thirds, proposal of height {:?}, round {:?}",
-                    proposal.height, proposal.round
-                )));
+                return Err(ConsensusError::AggregatedSignatureErr {
+                    height: proposal.height,
+                    round:  polc.lock_round,
+                    reason: format!(
+                        "PoLC of proposal round {} is not above threshold",
+                        proposal.round
+                    ),
+                });
             }
 
             self.verify_aggregated_signature(
@@ -698,13 +939,7 @@ where
                 polc.lock_votes.signature.clone(),
                 polc.lock_votes.to_vote(),
                 VoteType::Prevote,
-            )
-            .map_err(|err| {
-                ConsensusError::AggregatedSignatureErr(format!(
-                    "{:?} proposal of height {:?}, round {:?}",
-                    err, proposal.height, proposal.round
-                ))
-            })?;
+            )?;
             Some(polc.lock_round)
         } else {
             None
@@ -720,6 +955,14 @@ where
             signed_proposal.clone(),
         )?;
 
+        self.events.publish(ConsensusEvent::ProposalReceived {
+            height: proposal_height,
+            round: proposal_round,
+            block_hash: hash.clone(),
+            proposer: proposal.proposer.clone(),
+            lock_round,
+        });
+
         info!(
             "Overlord: state trigger SMR proposal height {}, round {}, hash {:?}",
             self.height,
@@ -766,14 +1009,16 @@ where
             })),
         );
 
-        let signed_vote = self.sign_vote(Vote {
-            height:     self.height,
-            round:      self.round,
-            vote_type:  vote_type.clone(),
-            block_hash: hash.clone(),
-        })?;
-
-        self.save_wal_with_lock_round(vote_type.clone().into(), lock_round)
+        let signed_vote = self
+            .sign_vote(
+                Vote {
+                    height:     self.height,
+                    round:      self.round,
+                    vote_type:  vote_type.clone(),
+                    block_hash: hash.clone(),
+                },
+                lock_round,
+            )
             .await?;
 
         if self.is_leader {
@@ -810,9 +1055,12 @@ where
         };
 
         let signature = self
-            .util
-            .sign(self.util.hash(Bytes::from(rlp::encode(&choke.to_hash()))))
-            .map_err(|err| ConsensusError::CryptoErr(format!("sign choke error {:?}", err)))?;
+            .sign_with_watermark(
+                Step::Brake,
+                lock_round,
+                self.signing_hash(MsgTag::Choke, &choke.to_hash()),
+            )
+            .await?;
         let signed_choke = SignedChoke {
             signature,
             choke,
@@ -825,8 +1073,6 @@ where
         );
 
         self.chokes.insert(self.round, signed_choke.clone());
-        self.save_wal_with_lock_round(Step::Brake, lock_round)
-            .await?;
         self.broadcast(Context::new(), OverlordMsg::SignedChoke(signed_choke))
             .await;
         self.check_choke_above_threshold()?;
@@ -867,7 +1113,10 @@ where
         {
             tmp.to_owned()
         } else {
-            return Err(ConsensusError::StorageErr("Lose precommit QC".to_string()));
+            return Err(ConsensusError::StateErr(format!(
+                "Lose precommit QC height {}, round {}",
+                self.height, self.round
+            )));
         };
 
         let polc = Some(WalLock {
@@ -888,7 +1137,7 @@ where
         let commit = Commit {
             height,
             content,
-            proof,
+            proof: proof.clone(),
         };
 
         let ctx = Context::new();
@@ -896,11 +1145,23 @@ where
             .function
             .commit(ctx.clone(), height, commit)
             .await
-            .map_err(|err| ConsensusError::Other(format!("commit error {:?}", err)))?;
+            .map_err(|source| ConsensusError::ApplicationErr {
+                method: "commit",
+                height,
+                source,
+            })?;
+
+        self.election.on_commit(height, &hash);
+        self.events.publish(ConsensusEvent::Commit {
+            height,
+            round: proof.round,
+            block_hash: hash,
+            proof,
+        });
 
         let mut auth_list = status.authority_list.clone();
         self.authority.update(&mut auth_list);
-        let cost = Instant::now() - self.height_start;
+        let cost = self.clock.now() - self.height_start;
 
         info!(
             "Overlord: achieve consensus in height {}, costs {} round {:?} time",
@@ -912,7 +1173,9 @@ where
         if self.next_proposer(status.height, INIT_ROUND)?
             && cost < Duration::from_millis(self.block_interval)
         {
-            Delay::new(Duration::from_millis(self.block_interval) - cost).await;
+            self.clock
+                .delay(Duration::from_millis(self.block_interval) - cost)
+                .await;
         }
 
         self.goto_new_height(ctx, status).await?;
@@ -964,18 +1227,61 @@ where
         );
 
         // All the votes must pass the verification of signature and address before be saved into
-        // vote collector.
-        let signature = signed_vote.signature.clone();
+        // vote collector. The address of a future height vote is verified by the authority list of
+        // that height. If the list is not available yet, save the vote unverified, and it will be
+        // re-checked as the state goes to that height. The signatures are verified in batches off
+        // the state loop, unless the vote has been verified before.
+        if !self.fetch_authority(ctx.clone(), height).await {
+            self.votes.insert_vote(
+                signed_vote.get_hash(),
+                signed_vote.clone(),
+                signed_vote.voter,
+            );
+            return Ok(());
+        }
+        self.verify_address(height, &signed_vote.voter)?;
+        let vote = PendingVote {
+            ctx,
+            hash: self.signing_hash(MsgTag::Vote, &signed_vote.vote),
+            signed_vote,
+        };
+        let key = vote.key();
+        if self.verified.lock().contains(&key) {
+            return self.handle_verified_vote(vote.ctx, vote.signed_vote).await;
+        }
+
+        // A vote which is being verified is a duplicate.
+        if self.verifying.insert(key) {
+            self.pending_votes.push(vote);
+        }
+        Ok(())
+    }
+
+    /// Handle a signed vote whose signature has been verified. Save it into the vote collector,
+    /// then make a statistic of the current votes and generate a QC if possible.
+    async fn handle_verified_vote(
+        &mut self,
+        ctx: Context,
+        signed_vote: SignedVote,
+    ) -> ConsensusResult<()> {
+        let height = signed_vote.get_height();
+        let round = signed_vote.get_round();
         let voter = signed_vote.voter.clone();
-        let vote = signed_vote.vote.clone();
-        self.verify_signature(
-            ctx.clone(),
-            self.util.hash(Bytes::from(rlp::encode(&vote))),
-            signature,
-            &voter,
-            MsgType::SignedVote,
-        )?;
-        self.verify_address(&voter)?;
+        let vote_type = if signed_vote.is_prevote() {
+            VoteType::Prevote
+        } else {
+            VoteType::Precommit
+        };
+
+        // The state may have gone forward during the verification.
+        if self.filter_message(height, round) {
+            return Ok(());
+        }
+
+        if let Some(evidence) = self.votes.check_conflict(&signed_vote) {
+            warn!("Overlord: state detects an equivocation {}", evidence);
+            self.report_evidence(ctx.clone(), evidence);
+        }
 
         // Check if the quorum certificate has generated before check whether there is a hash that
         // vote weight is above the threshold. If no hash achieved this, return directly.
@@ -1011,6 +1317,7 @@ where
         );
 
         self.votes.set_qc(qc.clone());
+        self.events.publish(ConsensusEvent::from_qc(qc.clone()));
 
         info!(
             "Overlord: state broadcast a {:?} QC, height {}, round {}, hash {:?}",
@@ -1101,6 +1408,16 @@ where
                         "Overlord: state receive a future QC, height {}, round {}",
                         vote_height, vote_round,
                     );
+                    // Save the QC unverified if the authority list of its height is not
+                    // available yet. It will be re-checked as the state goes to that height.
+                    if self.fetch_authority(ctx.clone(), vote_height).await {
+                        self.verify_aggregated_signature(
+                            ctx,
+                            aggregated_vote.signature.clone(),
+                            aggregated_vote.to_vote(),
+                            qc_type,
+                        )?;
+                    }
                     self.votes.set_qc(aggregated_vote);
                 } else {
                     warn!("Overlord: state receive a much higher aggregated vote");
@@ -1140,6 +1457,16 @@ where
             qc_type.clone(),
         )?;
 
+        // Publish the QC only when it is received for the first time.
+        if self
+            .votes
+            .get_qc_by_id(vote_height, vote_round, qc_type.clone())
+            .is_err()
+        {
+            self.events
+                .publish(ConsensusEvent::from_qc(aggregated_vote.clone()));
+        }
+
         // Check if the block hash has been verified.
         let qc_hash = aggregated_vote.block_hash.clone();
         self.votes.set_qc(aggregated_vote);
@@ -1207,6 +1534,7 @@ where
         } else if let Some(block_hash) = self.counting_vote(vote_type.clone())? {
             let qc = self.generate_qc(block_hash.clone(), vote_type.clone())?;
             self.votes.set_qc(qc.clone());
+            self.events.publish(ConsensusEvent::from_qc(qc.clone()));
 
             info!(
                 "Overlord: state broadcast a {:?} QC, height {}, round {}, hash {:?}",
@@ -1276,15 +1604,6 @@ where
         ctx: Context,
         signed_choke: SignedChoke,
     ) -> ConsensusResult<()> {
-        // verify signature
-        let signature = signed_choke.signature.clone();
-        let hash = self
-            .util
-            .hash(Bytes::from(rlp::encode(&signed_choke.choke.to_hash())));
-        self.util
-            .verify_signature(signature, hash, signed_choke.address.clone())
-            .map_err(|err| ConsensusError::CryptoErr(format!("{:?}", err)))?;
-
         let choke = signed_choke.choke.clone();
         let choke_height = choke.height;
         let choke_round = choke.round;
@@ -1298,6 +1617,26 @@ where
             return Ok(());
         }
 
+        // verify signature
+        let signature = signed_choke.signature.clone();
+        let hash = self.signing_hash(MsgTag::Choke, &signed_choke.choke.to_hash());
+        let key = (
+            hash.clone(),
+            signature.clone(),
+            signed_choke.address.clone(),
+        );
+        self.verify_cached(key, || {
+            self.util
+                .verify_signature(signature, hash, signed_choke.address.clone())
+                .map_err(|source| ConsensusError::InvalidSignature {
+                    height: signed_choke.choke.height,
+                    round: signed_choke.choke.round,
+                    address: Some(signed_choke.address.clone()),
+                    source,
+                })
+        })?;
+        self.verify_address(choke_height, &signed_choke.address)?;
+
         info!(
             "Overlord: state receive a choke of height {}, round {}, from {:?}",
             choke_height,
@@ -1327,21 +1666,43 @@ where
         aggregated_choke: AggregatedChoke,
     ) -> ConsensusResult<()> {
         // verify is above threshold.
-        if aggregated_choke.len() * 3 <= self.authority.len() * 2 {
-            return Err(ConsensusError::BrakeErr(
-                "choke qc is not above threshold".to_string(),
-            ));
+        let bitmap = &aggregated_choke.signature.address_bitmap;
+        if !self.authority.is_above_threshold(bitmap)? {
+            return Err(ConsensusError::AggregatedSignatureErr {
+                height: aggregated_choke.height,
+                round:  aggregated_choke.round,
+                reason: "Choke QC is not above threshold".to_string(),
+            });
         }
 
         // verify aggregated signature.
         let choke = aggregated_choke.to_hash();
-        let choke_hash = self.util.hash(Bytes::from(rlp::encode(&choke)));
-        let voters = aggregated_choke.voters.clone();
-        self.util
-            .verify_aggregated_signature(aggregated_choke.signature.clone(), choke_hash, voters)
-            .map_err(|err| {
-                ConsensusError::CryptoErr(format!("choke qc signature error {:?}", err))
-            })?;
+        let choke_hash = self.signing_hash(MsgTag::Choke, &choke);
+        let key = (
+            choke_hash.clone(),
+            aggregated_choke.signature.signature.clone(),
+            bitmap.clone(),
+        );
+        self.verify_cached(key, || {
+            let mut voters = self.authority.get_voters(bitmap)?;
+            voters.sort();
+            self.util
+                .verify_aggregated_signature(
+                    aggregated_choke.signature.signature.clone(),
+                    choke_hash,
+                    voters,
+                )
+                .map_err(|source| ConsensusError::InvalidSignature {
+                    height: choke.height,
+                    round: choke.round,
+                    address: None,
+                    source,
+                })
+        })?;
+        if self.chokes.get_qc(choke.round).is_none() {
+            self.events
+                .publish(ConsensusEvent::ChokeQC(aggregated_choke.clone()));
+        }
         self.chokes.set_qc(choke.round, aggregated_choke);
 
         self.state_machine.trigger(SMRTrigger {
@@ -1376,17 +1737,10 @@ where
             voters.push(vote.voter);
         }
 
-        let set = voters.iter().cloned().collect::<HashSet<_>>();
-        let mut bit_map = BitVec::from_elem(self.authority.len(), false);
-        for (index, addr) in self.authority.get_addres_ref().iter().enumerate() {
-            if set.contains(addr) {
-                bit_map.set(index, true);
-            }
-        }
-
+        let address_bitmap = self.authority.get_bitmap(&voters);
         let aggregated_signature = AggregatedSignature {
-            signature:      self.aggregate_signatures(signatures, voters)?,
-            address_bitmap: Bytes::from(bit_map.to_bytes()),
+            signature: self.aggregate_signatures(signatures, voters)?,
+            address_bitmap,
         };
         let qc = AggregatedVote {
             signature: aggregated_signature,
@@ -1416,10 +1770,11 @@ where
                 && self
                     .verify_signature(
                         ctx.clone(),
-                        self.util.hash(Bytes::from(rlp::encode(&proposal))),
+                        proposal.height,
+                        proposal.round,
+                        self.signing_hash(MsgTag::Proposal, &proposal),
                         signature,
                         &proposal.proposer,
-                        MsgType::SignedProposal,
                     )
                     .is_ok()
             {
@@ -1440,13 +1795,14 @@ where
             if self
                 .verify_signature(
                     Context::new(),
-                    self.util.hash(Bytes::from(rlp::encode(&vote))),
+                    vote.height,
+                    vote.round,
+                    self.signing_hash(MsgTag::Vote, &vote),
                     signature,
                     &voter,
-                    MsgType::SignedVote,
                 )
                 .is_ok()
-                && self.verify_address(&voter).is_ok()
+                && self.verify_address(sv.get_height(), &voter).is_ok()
             {
                 self.votes.insert_vote(sv.get_hash(), sv, voter);
             }
@@ -1475,7 +1831,9 @@ where
     /// If self is not the proposer of the height and round, set leader address as the proposer
     /// address.
     fn is_proposer(&mut self) -> ConsensusResult<bool> {
-        let proposer = self.authority.get_proposer(self.height, self.round)?;
+        let proposer =
+            self.authority
+                .get_proposer(self.election.as_ref(), self.height, self.round)?;
 
         if proposer == self.address {
             info!(
@@ -1499,16 +1857,25 @@ where
     }
 
     fn next_proposer(&self, height: u64, round: u64) -> ConsensusResult<bool> {
-        let proposer = self.authority.get_proposer(height, round)?;
+        let proposer = self
+            .authority
+            .get_proposer(self.election.as_ref(), height, round)?;
         Ok(self.address == proposer)
     }
 
-    fn sign_proposal(&self, proposal: Proposal<T>) -> ConsensusResult<SignedProposal<T>> {
+    async fn sign_proposal(
+        &mut self,
+        proposal: Proposal<T>,
+        lock_round: Option<u64>,
+    ) -> ConsensusResult<SignedProposal<T>> {
         debug!("Overlord: state sign a proposal");
         let signature = self
-            .util
-            .sign(self.util.hash(Bytes::from(rlp::encode(&proposal))))
-            .map_err(|err| ConsensusError::CryptoErr(format!("{:?}", err)))?;
+            .sign_with_watermark(
+                Step::Propose,
+                lock_round,
+                self.signing_hash(MsgTag::Proposal, &proposal),
+            )
+            .await?;
 
         Ok(SignedProposal {
             signature,
@@ -1516,12 +1883,19 @@ where
         })
     }
 
-    fn sign_vote(&self, vote: Vote) -> ConsensusResult<SignedVote> {
+    async fn sign_vote(
+        &mut self,
+        vote: Vote,
+        lock_round: Option<u64>,
+    ) -> ConsensusResult<SignedVote> {
         debug!("Overlord: state sign a vote");
         let signature = self
-            .util
-            .sign(self.util.hash(Bytes::from(rlp::encode(&vote))))
-            .map_err(|err| ConsensusError::CryptoErr(format!("{:?}", err)))?;
+            .sign_with_watermark(
+                vote.vote_type.clone().into(),
+                lock_round,
+                self.signing_hash(MsgTag::Vote, &vote),
+            )
+            .await?;
 
         Ok(SignedVote {
             voter: self.address.clone(),
@@ -1530,6 +1904,63 @@ where
         })
     }
 
+    /// Get the hash of a consensus message to sign or verify, which is separated by the chain id
+    /// and the message type.
+    fn signing_hash<M: rlp::Encodable>(&self, tag: MsgTag, msg: &M) -> Hash {
+        self.util
+            .hash(signing_payload(self.chain_id.as_ref(), tag, msg))
+    }
+
+    /// Sign a message hash of the step under the signing watermark, so that self never signs two
+    /// different messages of the same height, round and step, even if self restarts from the wal.
+    /// The watermark is saved into the wal with the step and the lock before signing. An identical
+    /// message re-uses the latest signature for retransmission.
+    async fn sign_with_watermark(
+        &mut self,
+        step: Step,
+        lock_round: Option<u64>,
+        hash: Hash,
+    ) -> ConsensusResult<Signature> {
+        let watermark = Watermark {
+            height: self.height,
+            round:  self.round,
+            step:   step.clone(),
+            hash:   hash.clone(),
+        };
+
+        let identical = match &self.watermark {
+            Some(latest) => latest.check(&watermark)?,
+            None => false,
+        };
+        if identical {
+            if let Some(signature) = &self.last_signature {
+                debug!("Overlord: state re-use the signature of {}", watermark);
+                return Ok(signature.clone());
+            }
+        } else {
+            // Save the new watermark, and restore the old one if the save fails, so that a retry
+            // saves it again rather than signs as an identical one.
+            let latest = self.watermark.replace(watermark);
+            if let Err(err) = self.save_wal_with_lock_round(step, lock_round).await {
+                self.watermark = latest;
+                return Err(err);
+            }
+            self.last_signature = None;
+        }
+
+        let signature =
+            self.signer
+                .sign(hash)
+                .await
+                .map_err(|source| ConsensusError::CryptoErr {
+                    height: self.height,
+                    round: self.round,
+                    source,
+                })?;
+        self.last_signature = Some(signature.clone());
+        Ok(signature)
+    }
+
     fn aggregate_signatures(
         &self,
         signatures: Vec<Signature>,
@@ -1548,7 +1979,11 @@ where
         let signature = self
             .util
             .aggregate_signatures(signatures, voters)
-            .map_err(|err| ConsensusError::CryptoErr(format!("{:?}", err)))?;
+            .map_err(|source| ConsensusError::CryptoErr {
+                height: self.height,
+                round: self.round,
+                source,
+            })?;
         Ok(signature)
     }
 
@@ -1556,18 +1991,24 @@ where
     fn verify_signature(
         &self,
         ctx: Context,
+        height: u64,
+        round: u64,
         hash: Hash,
         signature: Signature,
         address: &Address,
-        msg_type: MsgType,
     ) -> ConsensusResult<()> {
         debug!("Overlord: state verify a signature");
-        self.util
-            .verify_signature(signature, hash, address.to_owned())
-            .map_err(|err| {
-                ConsensusError::CryptoErr(format!("{:?} signature error {:?}", msg_type, err))
-            })?;
-        Ok(())
+        let key = (hash.clone(), signature.clone(), address.clone());
+        self.verify_cached(key, || {
+            self.util
+                .verify_signature(signature, hash, address.to_owned())
+                .map_err(|source| ConsensusError::InvalidSignature {
+                    height,
+                    round,
+                    address: Some(address.to_owned()),
+                    source,
+                })
+        })
     }
 
     #[tracing_span(kind = "overlord")]
@@ -1579,61 +2020,135 @@ where
         vote_type: VoteType,
     ) -> ConsensusResult<()> {
         debug!("Overlord: state verify an aggregated signature");
-        if !self
-            .authority
-            .is_above_threshold(&signature.address_bitmap)?
-        {
-            return Err(ConsensusError::AggregatedSignatureErr(format!(
-                "{:?} QC of height {}, round {} is not above threshold",
-                vote_type, self.height, self.round
-            )));
-        }
+        let hash = self.signing_hash(MsgTag::Vote, &vote);
+        let key = (
+            hash.clone(),
+            signature.signature.clone(),
+            signature.address_bitmap.clone(),
+        );
+        self.verify_cached(key, || {
+            let authority = self.get_authority(vote.height)?;
+            if !authority.is_above_threshold(&signature.address_bitmap)? {
+                return Err(ConsensusError::AggregatedSignatureErr {
+                    height: vote.height,
+                    round:  vote.round,
+                    reason: format!("{:?} QC is not above threshold", vote_type),
+                });
+            }
 
-        let mut voters = self.authority.get_voters(&signature.address_bitmap)?;
-        voters.sort();
+            let mut voters = authority.get_voters(&signature.address_bitmap)?;
+            voters.sort();
 
-        let pretty_voter = voters
-            .iter()
-            .map(|addr| hex::encode(addr.clone()))
-            .collect::<Vec<_>>();
+            let pretty_voter = voters
+                .iter()
+                .map(|addr| hex::encode(addr.clone()))
+                .collect::<Vec<_>>();
 
-        info!(
-            "Overlord: state verify aggregated signature, height {}, round {}, voters {:?}",
-            self.height, self.round, pretty_voter
-        );
+            info!(
+                "Overlord: state verify aggregated signature, height {}, round {}, voters {:?}",
+                vote.height, vote.round, pretty_voter
+            );
 
-        self.util
-            .verify_aggregated_signature(
-                signature.signature,
-                self.util.hash(Bytes::from(rlp::encode(&vote))),
-                voters,
-            )
-            .map_err(|err| {
-                ConsensusError::AggregatedSignatureErr(format!(
-                    "{:?} aggregate signature error {:?}",
-                    vote_type, err
-                ))
-            })?;
+            self.util
+                .verify_aggregated_signature(signature.signature, hash, voters)
+                .map_err(|source| ConsensusError::InvalidSignature {
+                    height: vote.height,
+                    round: vote.round,
+                    address: None,
+                    source,
+                })
+        })
+    }
+
+    /// Run the verification unless the key has been verified, and cache the key if it passes. A
+    /// repeated message, such as a QC embedded in a PoLC or a choke, is verified only once. The key
+    /// includes the signature and the signer, so a forged signature of a verified message is still
+    /// verified by the cryptography.
+    fn verify_cached<V>(&self, key: VerifyKey, verify: V) -> ConsensusResult<()>
+    where
+        V: FnOnce() -> ConsensusResult<()>,
+    {
+        if self.verified.lock().contains(&key) {
+            debug!("Overlord: state hit the verify cache");
+            return Ok(());
+        }
+
+        verify()?;
+        self.verified.lock().insert(key);
         Ok(())
     }
 
     fn verify_proposer(&self, height: u64, round: u64, address: &Address) -> ConsensusResult<()> {
         debug!("Overlord: state verify a proposer");
-        self.verify_address(address)?;
-        if address != &self.authority.get_proposer(height, round)? {
-            return Err(ConsensusError::ProposalErr("Invalid proposer".to_string()));
+        self.verify_address(height, address)?;
+        if address
+            != &self
+                .get_authority(height)?
+                .get_proposer(self.election.as_ref(), height, round)?
+        {
+            return Err(ConsensusError::InvalidProposer {
+                height,
+                round,
+                address: address.to_owned(),
+            });
         }
         Ok(())
     }
 
-    /// Check whether the given address is included in the corresponding authority list.
-    fn verify_address(&self, address: &Address) -> ConsensusResult<()> {
-        if !self.authority.contains(address) {
-            return Err(ConsensusError::InvalidAddress);
+    /// Check whether the given address is included in the authority list of the given height.
+    fn verify_address(&self, height: u64, address: &Address) -> ConsensusResult<()> {
+        if !self.get_authority(height)?.contains(address) {
+            return Err(ConsensusError::InvalidAddress(address.to_owned()));
         }
         Ok(())
     }
 
+    /// Get the authority manage of the given height. The current height uses the authority list
+    /// from the status, and other heights use the one fetched by `fetch_authority()`.
+    fn get_authority(&self, height: u64) -> ConsensusResult<&AuthorityManage> {
+        if height == self.height {
+            return Ok(&self.authority);
+        }
+
+        self.authority_cache
+            .get(&height)
+            .and_then(Option::as_ref)
+            .ok_or_else(|| {
+                ConsensusError::StateErr(format!("no authority list of height {}", height))
+            })
+    }
+
+    /// Fetch the authority list of a height other than the current one by `get_authority_list()`
+    /// and cache it, return whether the list is available. The application may be unable to
+    /// answer before the previous heights are committed, so a failure is cached as well to avoid
+    /// asking again for every message. The failures are cleared as the state goes to a new height.
+    async fn fetch_authority(&mut self, ctx: Context, height: u64) -> bool {
+        if height == self.height {
+            return true;
+        }
+        if let Some(authority) = self.authority_cache.get(&height) {
+            return authority.is_some();
+        }
+
+        let authority = match self.function.get_authority_list(ctx, height).await {
+            Ok(mut authority_list) => {
+                let mut authority = AuthorityManage::new();
+                authority.update(&mut authority_list);
+                Some(authority)
+            }
+            Err(err) => {
+                debug!(
+                    "Overlord: state can not get the authority list of height {}, error {:?}",
+                    height, err
+                );
+                None
+            }
+        };
+        let available = authority.is_some();
+        self.authority_cache.insert(height, authority);
+        available
+    }
+
     async fn transmit(&self, ctx: Context, msg: OverlordMsg<T>) {
         debug!(
             "Overlord: state transmit a message to leader height {}, round {}",
@@ -1689,30 +2204,92 @@ where
         self.function.report_error(ctx, err);
     }
 
+    fn report_evidence(&self, ctx: Context, evidence: Evidence<T>) {
+        self.function.report_evidence(ctx, evidence);
+    }
+
+    /// Punish the peer who sends the message if the error indicates a misbehaviour.
+    fn punish(&self, ctx: &Context, err: &ConsensusError) {
+        if let (Some(peer), Some(misbehavior)) = (get_peer(ctx), misbehavior_of(err)) {
+            self.peers.punish(peer, misbehavior);
+        }
+    }
+
+    /// Report the pending penalties of peers to the application.
+    fn report_penalties(&self) {
+        for penalty in self.peers.take_penalties() {
+            let ctx = with_peer(&Context::new(), penalty.peer.clone());
+            self.function.report_penalty(ctx, penalty);
+        }
+    }
+
+    /// Check whether the signed proposal conflicts with the one cached in the proposal collector.
+    /// Since proposals of the future height or round are cached without verification, both
+    /// signatures should be verified before reporting the evidence.
+    fn check_proposal_conflict(&self, ctx: Context, signed_proposal: &SignedProposal<T>) {
+        let height = signed_proposal.proposal.height;
+        let round = signed_proposal.proposal.round;
+        if let Some(evidence) = self
+            .proposals
+            .check_conflict(height, round, signed_proposal)
+        {
+            if let Evidence::DoubleProposal(first, second) = &evidence {
+                for sp in [first, second].iter() {
+                    if self
+                        .verify_signature(
+                            ctx.clone(),
+                            height,
+                            round,
+                            self.signing_hash(MsgTag::Proposal, &sp.proposal),
+                            sp.signature.clone(),
+                            &sp.proposal.proposer,
+                        )
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+
+            warn!("Overlord: state detects an equivocation {}", evidence);
+            self.report_evidence(ctx, evidence);
+        }
+    }
+
     fn check_choke_above_threshold(&mut self) -> ConsensusResult<()> {
         self.chokes.print_round_choke_log(self.round);
-        if let Some(round) = self.chokes.max_round_above_threshold(self.authority.len()) {
+        if let Some(round) = self.chokes.max_round_above_threshold(&self.authority) {
             if round < self.round {
                 return Ok(());
             }
 
             info!("Overlord: round {} chokes above threshold", round);
 
-            // aggregate chokes.
-            let signed_chokes = self.chokes.get_chokes(round).unwrap();
+            // aggregate chokes, the signatures are sorted by the voter's address and each voter
+            // is aggregated only once.
+            let mut signed_chokes = self.chokes.get_chokes(round).unwrap();
+            signed_chokes.sort_by(|a, b| a.address.cmp(&b.address));
+            signed_chokes.dedup_by(|a, b| a.address == b.address);
+
             let mut sigs = Vec::with_capacity(signed_chokes.len());
             let mut voters = Vec::with_capacity(signed_chokes.len());
-            for sc in signed_chokes.iter() {
-                sigs.push(sc.signature.clone());
-                voters.push(sc.address.clone());
+            for sc in signed_chokes.into_iter() {
+                sigs.push(sc.signature);
+                voters.push(sc.address);
             }
-            let sig = self.aggregate_signatures(sigs, voters.clone())?;
-            self.chokes.set_qc(round, AggregatedChoke {
+            let address_bitmap = self.authority.get_bitmap(&voters);
+            let qc = AggregatedChoke {
                 height: self.height,
-                signature: sig,
                 round,
-                voters,
-            });
+                signature: AggregatedSignature {
+                    signature: self.aggregate_signatures(sigs, voters)?,
+                    address_bitmap,
+                },
+            };
+            if self.chokes.get_qc(round).is_none() {
+                self.events.publish(ConsensusEvent::ChokeQC(qc.clone()));
+            }
+            self.chokes.set_qc(round, qc);
 
             info!(
                 "Overlord: state trigger SMR go on {} round of height {}",
@@ -1738,6 +2315,8 @@ where
         let height = self.height;
         let round = self.round;
         let function = Arc::clone(&self.function);
+        let reporter = Arc::clone(&self.function);
+        let report_ctx = ctx.clone();
         let resp_tx = self.resp_tx.clone();
 
         trace::custom(
@@ -1749,7 +2328,7 @@ where
             })),
         );
 
-        tokio::spawn(async move {
+        let handle = spawn_with_handle(self.spawner.as_ref(), async move {
             if let Err(e) =
                 check_current_block(ctx, function, height, round, hash.clone(), block, resp_tx)
                     .await
@@ -1763,8 +2342,65 @@ where
                 );
 
                 error!("Overlord: state check block failed: {:?}", e);
+                reporter.report_error(report_ctx, e);
             }
         });
+        self.check_tasks.push(handle);
+    }
+
+    /// Spawn a task to verify the pending votes in a batch, if the batch is full or it is forced.
+    /// The signatures are verified by `Spawner::spawn_blocking()`, off the async executor.
+    fn flush_votes(&mut self, force: bool) {
+        if self.pending_votes.is_empty() || (!force && self.pending_votes.len() < VERIFY_BATCH_SIZE)
+        {
+            return;
+        }
+
+        let votes = mem::replace(&mut self.pending_votes, Vec::new());
+        let id = self.verify_id;
+        self.verify_id += 1;
+        debug!("Overlord: state verify a batch of {} votes", votes.len());
+
+        let crypto = Arc::clone(&self.util);
+        let votes_tx = self.votes_tx.clone();
+        let verify = run_blocking(self.spawner.as_ref(), move || {
+            verify_votes(crypto.as_ref(), votes)
+        });
+        let handle = spawn_with_handle(self.spawner.as_ref(), async move {
+            match verify.await {
+                Some(votes) => {
+                    let _ = votes_tx.unbounded_send(VerifiedVotes { id, votes });
+                }
+                None => error!("Overlord: state verify task {} is canceled", id),
+            }
+        });
+        self.verify_tasks.insert(id, handle);
+    }
+
+    /// Handle a batch of votes verified by the verify task.
+    async fn handle_verified_votes(&mut self, verified: VerifiedVotes) {
+        self.verify_tasks.remove(&verified.id);
+        for (vote, res) in verified.votes.into_iter() {
+            let key = vote.key();
+            self.verifying.remove(&key);
+            let ctx = vote.ctx.clone();
+
+            let res = match res {
+                Ok(()) => {
+                    self.verified.lock().insert(key);
+                    if !self.consensus_power {
+                        continue;
+                    }
+                    self.handle_verified_vote(vote.ctx, vote.signed_vote).await
+                }
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = res {
+                self.punish(&ctx, &e);
+                error!("Overlord: state handle signed vote error {:?}", e);
+            }
+        }
     }
 
     async fn save_wal(&mut self, step: Step, lock: Option<WalLock<T>>) -> ConsensusResult<()> {
@@ -1774,29 +2410,28 @@ where
             step: step.clone(),
             from: self.update_from_where.clone(),
             lock,
+            watermark: self.watermark.clone(),
         };
 
-        self.wal
-            .save(Bytes::from(rlp::encode(&wal_info)))
-            .await
-            .map_err(|e| {
-                trace::error(
-                    "save_wal".to_string(),
-                    Some(json!({
-                        "height": self.height,
-                        "round": self.round,
-                        "step": step.to_string(),
-                        "error": e.to_string(),
-                    })),
-                );
+        self.wal.save(wal_info.to_wal_bytes()).await.map_err(|e| {
+            trace::error(
+                "save_wal".to_string(),
+                Some(json!({
+                    "height": self.height,
+                    "round": self.round,
+                    "step": step.to_string(),
+                    "error": e.to_string(),
+                })),
+            );
 
-                error!("Overlord: state save wal error {:?}", e);
-                ConsensusError::SaveWalErr {
-                    height: self.height,
-                    round:  self.round,
-                    step:   step.to_string(),
-                }
-            })?;
+            error!("Overlord: state save wal error {:?}", e);
+            ConsensusError::SaveWalErr {
+                height: self.height,
+                round:  self.round,
+                step:   step.to_string(),
+                source: e,
+            }
+        })?;
         Ok(())
     }
 
@@ -1846,6 +2481,7 @@ where
         self.round = wal_info.round;
         self.is_leader = self.is_proposer()?;
         self.update_from_where = wal_info.from.clone();
+        self.watermark = wal_info.watermark.clone();
 
         // recover lock state
         if wal_info.lock.is_some() {
@@ -1885,14 +2521,17 @@ where
             .wal
             .load()
             .await
-            .map_err(|e| ConsensusError::LoadWalErr(e.to_string()))?;
+            .map_err(|e| match e.downcast::<ConsensusError>() {
+                Ok(err) => *err,
+                Err(e) => ConsensusError::WalErr(e),
+            })?;
 
         if tmp.is_none() {
             return Ok(None);
         }
 
-        let info: WalInfo<T> = rlp::decode(tmp.unwrap().as_ref())
-            .map_err(|e| ConsensusError::LoadWalErr(e.to_string()))?;
+        let info =
+            WalInfo::from_wal_bytes(tmp.unwrap().as_ref(), self.authority.get_authority_list())?;
         Ok(Some(info))
     }
 
@@ -1927,12 +2566,14 @@ where
             }
 
             FromWhere::ChokeQC(round) => {
-                let qc = self.chokes.get_qc(round).ok_or_else(|| {
-                    ConsensusError::BrakeErr(format!(
-                        "no choke qc height {} round {}",
-                        self.height, round
-                    ))
-                })?;
+                let qc = self
+                    .chokes
+                    .get_qc(round)
+                    .ok_or_else(|| ConsensusError::BrakeErr {
+                        height: self.height,
+                        round,
+                        reason: "no choke qc".to_string(),
+                    })?;
                 UpdateFrom::ChokeQC(qc)
             }
         };
@@ -2004,19 +2645,24 @@ async fn check_current_block<U: Consensus<T>, T: Codec>(
     block: T,
     tx: UnboundedSender<VerifyResp>,
 ) -> ConsensusResult<()> {
-    function
-        .check_block(ctx, height, hash.clone(), block)
-        .await
-        .map_err(|err| ConsensusError::Other(format!("check {} block error {:?}", height, err)))?;
+    let res = function.check_block(ctx, height, hash.clone(), block).await;
+    let reason = res.as_ref().err().map(|err| format!("{:?}", err));
 
-    debug!("Overlord: state check block {}", true);
+    debug!("Overlord: state check block {}", reason.is_none());
     tx.unbounded_send(VerifyResp {
         height,
         round,
         block_hash: hash,
-        is_pass: true,
+        is_pass: reason.is_none(),
+        reason,
+    })
+    .map_err(|e| ConsensusError::ChannelErr(e.to_string()))?;
+
+    res.map_err(|source| ConsensusError::CheckBlockErr {
+        height,
+        round,
+        source,
     })
-    .map_err(|e| ConsensusError::ChannelErr(e.to_string()))
 }
 
 fn mock_init_qc() -> AggregatedVote {
@@ -0,0 +1,242 @@
+use creep::Context;
+
+use crate::error::ConsensusError;
+use crate::types::{Hash, SignedVote};
+use crate::utils::verify_cache::VerifyKey;
+use crate::{ConsensusResult, Crypto};
+
+/// The max number of signed votes verified in a batch.
+pub(crate) const VERIFY_BATCH_SIZE: usize = 64;
+
+/// A signed vote waiting for the signature verification, with the hash of the vote.
+#[derive(Clone, Debug)]
+pub(crate) struct PendingVote {
+    pub(crate) ctx:         Context,
+    pub(crate) hash:        Hash,
+    pub(crate) signed_vote: SignedVote,
+}
+
+impl PendingVote {
+    /// Get the key of the vote in the verify cache.
+    pub(crate) fn key(&self) -> VerifyKey {
+        (
+            self.hash.clone(),
+            self.signed_vote.signature.clone(),
+            self.signed_vote.voter.clone(),
+        )
+    }
+}
+
+/// A batch of signed votes with the verification results, which is sent back to the state by the
+/// verify task.
+#[derive(Debug)]
+pub(crate) struct VerifiedVotes {
+    pub(crate) id:    u64,
+    pub(crate) votes: Vec<(PendingVote, ConsensusResult<()>)>,
+}
+
+/// Verify the signatures of the votes by `Crypto::batch_verify()`. If the crypto returns a wrong
+/// number of results, all the votes fail.
+pub(crate) fn verify_votes<C: Crypto>(
+    crypto: &C,
+    votes: Vec<PendingVote>,
+) -> Vec<(PendingVote, ConsensusResult<()>)> {
+    let signatures = votes
+        .iter()
+        .map(|vote| {
+            (
+                vote.signed_vote.signature.clone(),
+                vote.hash.clone(),
+                vote.signed_vote.voter.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let results = crypto.batch_verify(signatures);
+
+    if results.len() != votes.len() {
+        let reason = format!(
+            "batch verify returns {} results for {} signatures",
+            results.len(),
+            votes.len()
+        );
+        return votes
+            .into_iter()
+            .map(|vote| {
+                let err = ConsensusError::CryptoErr {
+                    height: vote.signed_vote.get_height(),
+                    round:  vote.signed_vote.get_round(),
+                    source: Box::new(ConsensusError::Other(reason.clone())),
+                };
+                (vote, Err(err))
+            })
+            .collect();
+    }
+
+    votes
+        .into_iter()
+        .zip(results.into_iter())
+        .map(|(vote, res)| {
+            let res = res.map_err(|source| ConsensusError::InvalidSignature {
+                height: vote.signed_vote.get_height(),
+                round: vote.signed_vote.get_round(),
+                address: Some(vote.signed_vote.voter.clone()),
+                source,
+            });
+            (vote, res)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(super) mod test {
+    use std::error::Error;
+
+    use bytes::Bytes;
+    use creep::Context;
+    use rand::random;
+
+    use crate::types::{Address, Hash, Signature, SignedVote, Vote, VoteType};
+    use crate::Crypto;
+
+    use super::{verify_votes, PendingVote};
+
+    /// A crypto whose signature is the blake2b hash of the message hash and the voter.
+    pub(in crate::state) struct BlakeCrypto;
+
+    impl BlakeCrypto {
+        fn digest(hash: &Hash, voter: &Address) -> Signature {
+            let mut state = blake2b_simd::State::new();
+            state.update(hash.as_ref());
+            state.update(voter.as_ref());
+            Bytes::from(state.finalize().as_bytes().to_vec())
+        }
+    }
+
+    impl Crypto for BlakeCrypto {
+        fn hash(&self, msg: Bytes) -> Hash {
+            Bytes::from(blake2b_simd::blake2b(msg.as_ref()).as_bytes().to_vec())
+        }
+
+        fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+            Ok(hash)
+        }
+
+        fn aggregate_signatures(
+            &self,
+            _signatures: Vec<Signature>,
+            _voters: Vec<Address>,
+        ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+            Ok(Signature::new())
+        }
+
+        fn verify_signature(
+            &self,
+            signature: Signature,
+            hash: Hash,
+            voter: Address,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            if signature == BlakeCrypto::digest(&hash, &voter) {
+                Ok(())
+            } else {
+                Err(Box::new(std::fmt::Error))
+            }
+        }
+
+        fn verify_aggregated_signature(
+            &self,
+            _aggregate_signature: Signature,
+            _msg_hash: Hash,
+            _voters: Vec<Address>,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Ok(())
+        }
+    }
+
+    /// A crypto which returns a wrong number of results in batch verification.
+    struct BrokenCrypto;
+
+    impl Crypto for BrokenCrypto {
+        fn hash(&self, msg: Bytes) -> Hash {
+            BlakeCrypto.hash(msg)
+        }
+
+        fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+            BlakeCrypto.sign(hash)
+        }
+
+        fn aggregate_signatures(
+            &self,
+            signatures: Vec<Signature>,
+            voters: Vec<Address>,
+        ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+            BlakeCrypto.aggregate_signatures(signatures, voters)
+        }
+
+        fn verify_signature(
+            &self,
+            signature: Signature,
+            hash: Hash,
+            voter: Address,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            BlakeCrypto.verify_signature(signature, hash, voter)
+        }
+
+        fn verify_aggregated_signature(
+            &self,
+            aggregate_signature: Signature,
+            msg_hash: Hash,
+            voters: Vec<Address>,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            BlakeCrypto.verify_aggregated_signature(aggregate_signature, msg_hash, voters)
+        }
+
+        fn batch_verify(
+            &self,
+            _signatures: Vec<(Signature, Hash, Address)>,
+        ) -> Vec<Result<(), Box<dyn Error + Send + Sync>>> {
+            vec![Ok(())]
+        }
+    }
+
+    pub(in crate::state) fn gen_pending_vote(valid: bool) -> PendingVote {
+        let vote = Vote {
+            height:     random::<u64>(),
+            round:      random::<u64>(),
+            vote_type:  VoteType::Prevote,
+            block_hash: Bytes::from((0..32).map(|_| random::<u8>()).collect::<Vec<_>>()),
+        };
+        let hash = BlakeCrypto.hash(Bytes::from(rlp::encode(&vote)));
+        let voter = Bytes::from((0..32).map(|_| random::<u8>()).collect::<Vec<_>>());
+        let signature = if valid {
+            BlakeCrypto::digest(&hash, &voter)
+        } else {
+            Bytes::from(vec![0u8; 64])
+        };
+
+        PendingVote {
+            ctx: Context::new(),
+            hash,
+            signed_vote: SignedVote {
+                signature,
+                vote,
+                voter,
+            },
+        }
+    }
+
+    #[test]
+    fn test_verify_votes() {
+        let votes = (0..10)
+            .map(|i| gen_pending_vote(i % 3 != 0))
+            .collect::<Vec<_>>();
+        let results = verify_votes(&BlakeCrypto, votes.clone());
+        assert_eq!(results.len(), votes.len());
+        for (i, (vote, res)) in results.into_iter().enumerate() {
+            assert_eq!(vote.signed_vote, votes[i].signed_vote);
+            assert_eq!(res.is_ok(), i % 3 != 0);
+        }
+
+        let results = verify_votes(&BrokenCrypto, votes);
+        assert!(results.iter().all(|(_, res)| res.is_err()));
+    }
+}
@@ -1,24 +1,27 @@
+use std::mem;
+use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::Duration;
 use std::{future::Future, pin::Pin};
 
 use derive_more::Display;
 use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::future::{join_all, BoxFuture};
 use futures::stream::{Stream, StreamExt};
-use futures::{FutureExt, SinkExt};
-use futures_timer::Delay;
+use futures::FutureExt;
 use log::{debug, error, info};
 
+use crate::runtime::{spawn_with_handle, JoinHandle};
 use crate::smr::smr_types::{SMREvent, SMRTrigger, TriggerSource, TriggerType};
 use crate::smr::{Event, SMRHandler};
-use crate::DurationConfig;
 use crate::{error::ConsensusError, ConsensusResult, INIT_HEIGHT, INIT_ROUND};
 use crate::{types::Hash, utils::timer_config::TimerConfig};
+use crate::{Clock, DurationConfig, Spawner};
 
 const MAX_TIMEOUT_COEF: u32 = 5;
 
 /// Overlord timer used futures timer which is powered by a timer heap. When monitor a SMR event,
-/// timer will get timeout interval from timer config, then set a delay. When the timeout expires,
+/// timer will get timeout interval from timer config, then set a delay by the clock. When the
+/// timeout expires,
 #[derive(Debug)]
 pub struct Timer {
     config:        TimerConfig,
@@ -28,6 +31,9 @@ pub struct Timer {
     state_machine: SMRHandler,
     height:        u64,
     round:         u64,
+    clock:         Arc<dyn Clock>,
+    spawner:       Arc<dyn Spawner>,
+    timeouts:      Vec<JoinHandle<()>>,
 }
 
 ///
@@ -44,7 +50,7 @@ impl Stream for Timer {
 
                 Poll::Ready(event) => {
                     if event.is_none() {
-                        return Poll::Ready(Some(ConsensusError::TimerErr(
+                        return Poll::Ready(Some(ConsensusError::ChannelErr(
                             "Channel dropped".to_string(),
                         )));
                     }
@@ -64,7 +70,7 @@ impl Stream for Timer {
 
                 Poll::Ready(event) => {
                     if event.is_none() {
-                        return Poll::Ready(Some(ConsensusError::TimerErr(
+                        return Poll::Ready(Some(ConsensusError::ChannelErr(
                             "Channel terminated".to_string(),
                         )));
                     }
@@ -88,6 +94,8 @@ impl Timer {
         state_machine: SMRHandler,
         interval: u64,
         config: Option<DurationConfig>,
+        clock: Arc<dyn Clock>,
+        spawner: Arc<dyn Spawner>,
     ) -> Self {
         let (tx, rx) = unbounded();
         let mut timer_config = TimerConfig::new(interval);
@@ -103,15 +111,36 @@ impl Timer {
             notify: rx,
             event,
             state_machine,
+            clock,
+            spawner,
+            timeouts: Vec::new(),
         }
     }
 
-    pub fn run(mut self) {
-        tokio::spawn(async move {
+    /// Run the timer by the spawner and return the join handle. The timer exits with `Ok(())` on
+    /// the stop event, or with an error if the event channel is broken. All the timeouts are
+    /// aborted and joined before the timer exits.
+    pub fn run(mut self) -> JoinHandle<ConsensusResult<()>> {
+        let spawner = Arc::clone(&self.spawner);
+        spawn_with_handle(spawner.as_ref(), async move {
+            let mut res = Ok(());
             while let Some(err) = self.next().await {
+                if let ConsensusError::ChannelErr(_) = err {
+                    error!("Overlord: timer fatal error {:?}", err);
+                    res = Err(err);
+                    break;
+                }
                 error!("Overlord: timer error {:?}", err);
             }
-        });
+
+            self.abort_timeouts();
+            join_all(self.timeouts.drain(..)).await;
+            res
+        })
+    }
+
+    fn abort_timeouts(&mut self) {
+        self.timeouts.iter().for_each(JoinHandle::abort);
     }
 
     fn set_timer(&mut self, event: SMREvent) -> ConsensusResult<()> {
@@ -125,6 +154,9 @@ impl Timer {
                 ..
             } => {
                 if height > self.height {
+                    // The timeouts of previous heights are useless, abort them.
+                    self.abort_timeouts();
+                    self.timeouts.clear();
                     self.height = height;
                 }
                 self.round = round;
@@ -151,11 +183,20 @@ impl Timer {
         }
 
         info!("Overlord: timer set {} timer", event);
-        let smr_timer = TimeoutInfo::new(interval, event, self.sender.clone());
-
-        tokio::spawn(async move {
-            smr_timer.await;
-        });
+        let smr_timer = TimeoutInfo::new(self.clock.delay(interval), event, self.sender.clone());
+        let handle = spawn_with_handle(self.spawner.as_ref(), smr_timer);
+        // Drop the handles of the fired timeouts, so that they do not pile up in a long height.
+        self.timeouts = mem::replace(&mut self.timeouts, Vec::new())
+            .into_iter()
+            .filter_map(|mut timeout| {
+                if timeout.is_finished() {
+                    None
+                } else {
+                    Some(timeout)
+                }
+            })
+            .collect();
+        self.timeouts.push(handle);
         Ok(())
     }
 
@@ -211,12 +252,12 @@ impl Timer {
     }
 }
 
-/// Timeout info which is a future consists of a `futures-timer Delay`, timeout info and a sender.
-/// When the timeout expires, future will send timeout info by sender.
-#[derive(Debug, Display)]
+/// Timeout info which is a future consists of a delay created by the clock, timeout info and a
+/// sender. When the timeout expires, future will send timeout info by sender.
+#[derive(Display)]
 #[display(fmt = "{:?}", info)]
 struct TimeoutInfo {
-    timeout: Delay,
+    timeout: BoxFuture<'static, ()>,
     info:    SMREvent,
     sender:  UnboundedSender<SMREvent>,
 }
@@ -225,15 +266,10 @@ impl Future for TimeoutInfo {
     type Output = ();
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        let msg = self.info.clone();
-        let mut tx = self.sender.clone();
-
         match self.timeout.poll_unpin(cx) {
             Poll::Pending => Poll::Pending,
             Poll::Ready(_) => {
-                tokio::spawn(async move {
-                    let _ = tx.send(msg).await;
-                });
+                let _ = self.sender.unbounded_send(self.info.clone());
                 Poll::Ready(())
             }
         }
@@ -241,24 +277,38 @@ impl Future for TimeoutInfo {
 }
 
 impl TimeoutInfo {
-    fn new(interval: Duration, event: SMREvent, tx: UnboundedSender<SMREvent>) -> Self {
+    fn new(
+        timeout: BoxFuture<'static, ()>,
+        event: SMREvent,
+        tx: UnboundedSender<SMREvent>,
+    ) -> Self {
         TimeoutInfo {
-            timeout: Delay::new(interval),
-            info:    event,
-            sender:  tx,
+            timeout,
+            info: event,
+            sender: tx,
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use futures::channel::mpsc::unbounded;
-    use futures::stream::StreamExt;
+    use std::sync::Arc;
+    use std::time::Duration;
 
+    use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+    use futures::{FutureExt, StreamExt};
+    use parking_lot::Mutex;
+
+    use crate::error::ConsensusError;
+    #[cfg(feature = "tokio-runtime")]
+    use crate::runtime::{SystemClock, TokioSpawner};
+    use crate::sim::Simulator;
     use crate::smr::smr_types::{FromWhere, SMREvent, SMRTrigger, TriggerSource, TriggerType};
     use crate::smr::{Event, SMRHandler};
     use crate::{timer::Timer, types::Hash};
+    use crate::{ConsensusResult, Spawner};
 
+    #[cfg(feature = "tokio-runtime")]
     async fn test_timer_trigger(input: SMREvent, output: SMRTrigger) {
         let (trigger_tx, mut trigger_rx) = unbounded();
         let (event_tx, event_rx) = unbounded();
@@ -267,6 +317,8 @@ mod test {
             SMRHandler::new(trigger_tx),
             3000,
             None,
+            Arc::new(SystemClock),
+            Arc::new(TokioSpawner),
         );
         event_tx.unbounded_send(input).unwrap();
 
@@ -297,6 +349,7 @@ mod test {
         }
     }
 
+    #[cfg(feature = "tokio-runtime")]
     #[tokio::test(threaded_scheduler)]
     async fn test_correctness() {
         // Test propose step timer.
@@ -339,6 +392,7 @@ mod test {
         .await;
     }
 
+    #[cfg(feature = "tokio-runtime")]
     #[tokio::test(threaded_scheduler)]
     async fn test_order() {
         let (trigger_tx, mut trigger_rx) = unbounded();
@@ -348,6 +402,8 @@ mod test {
             SMRHandler::new(trigger_tx),
             3000,
             None,
+            Arc::new(SystemClock),
+            Arc::new(TokioSpawner),
         );
 
         let new_round_event = SMREvent::NewRoundInfo {
@@ -406,4 +462,150 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_local_executor() {
+        let sim = Simulator::new(0);
+        let (trigger_tx, mut trigger_rx) = unbounded();
+        let (event_tx, event_rx) = unbounded();
+        let timer = Timer::new(
+            Event::new(event_rx),
+            SMRHandler::new(trigger_tx),
+            3000,
+            None,
+            sim.clock(),
+            sim.spawner(),
+        );
+        let _handle = timer.run();
+
+        let triggers = Arc::new(Mutex::new(Vec::new()));
+        let inner = Arc::clone(&triggers);
+        sim.spawn(
+            async move {
+                while let Some(res) = trigger_rx.next().await {
+                    inner.lock().push(res);
+                }
+            }
+            .boxed(),
+        );
+
+        event_tx
+            .unbounded_send(SMREvent::NewRoundInfo {
+                height:        0,
+                round:         0,
+                lock_round:    None,
+                lock_proposal: None,
+                new_interval:  None,
+                new_config:    None,
+                from_where:    FromWhere::PrecommitQC(0),
+            })
+            .unwrap();
+        assert!(sim.run_until(Duration::from_secs(60), || !triggers.lock().is_empty()));
+        assert_eq!(*triggers.lock(), vec![gen_output(
+            TriggerType::Proposal,
+            0,
+            0
+        )]);
+
+        event_tx.unbounded_send(SMREvent::Stop).unwrap();
+        sim.run_for(Duration::from_secs(60));
+        assert_eq!(triggers.lock().len(), 1);
+    }
+
+    #[test]
+    fn test_prune_fired_timeouts() {
+        let sim = Simulator::new(0);
+        let (trigger_tx, _trigger_rx) = unbounded();
+        let (_event_tx, event_rx) = unbounded();
+        let mut timer = Timer::new(
+            Event::new(event_rx),
+            SMRHandler::new(trigger_tx),
+            3000,
+            None,
+            sim.clock(),
+            sim.spawner(),
+        );
+        let prevote_event = SMREvent::PrevoteVote {
+            height:     0u64,
+            round:      0u64,
+            block_hash: Hash::new(),
+            lock_round: None,
+        };
+
+        timer.set_timer(prevote_event.clone()).unwrap();
+        timer.set_timer(prevote_event.clone()).unwrap();
+        assert_eq!(timer.timeouts.len(), 2);
+
+        // Both timeouts fire, only the new one is kept.
+        sim.run_for(Duration::from_secs(60));
+        timer.set_timer(prevote_event).unwrap();
+        assert_eq!(timer.timeouts.len(), 1);
+    }
+
+    type JoinResult = Arc<Mutex<Option<Option<ConsensusResult<()>>>>>;
+
+    fn run_timer(
+        sim: &Simulator,
+    ) -> (
+        UnboundedSender<SMREvent>,
+        UnboundedReceiver<SMRTrigger>,
+        JoinResult,
+    ) {
+        let (trigger_tx, trigger_rx) = unbounded();
+        let (event_tx, event_rx) = unbounded();
+        let timer = Timer::new(
+            Event::new(event_rx),
+            SMRHandler::new(trigger_tx),
+            3000,
+            None,
+            sim.clock(),
+            sim.spawner(),
+        );
+        let handle = timer.run();
+
+        let res = Arc::new(Mutex::new(None));
+        let inner = Arc::clone(&res);
+        sim.spawn(
+            async move {
+                *inner.lock() = Some(handle.await);
+            }
+            .boxed(),
+        );
+        (event_tx, trigger_rx, res)
+    }
+
+    #[test]
+    fn test_join_on_stop() {
+        let sim = Simulator::new(0);
+        let (event_tx, _trigger_rx, res) = run_timer(&sim);
+        event_tx
+            .unbounded_send(SMREvent::PrevoteVote {
+                height:     0u64,
+                round:      0u64,
+                block_hash: Hash::new(),
+                lock_round: None,
+            })
+            .unwrap();
+        sim.run_for(Duration::from_millis(1));
+        // The timer, the timeout and the joiner.
+        assert_eq!(sim.task_count(), 3);
+
+        event_tx.unbounded_send(SMREvent::Stop).unwrap();
+        sim.run_for(Duration::from_millis(1));
+        assert_eq!(*res.lock(), Some(Some(Ok(()))));
+        assert_eq!(sim.task_count(), 0);
+    }
+
+    #[test]
+    fn test_channel_dropped() {
+        let sim = Simulator::new(0);
+        let (event_tx, _trigger_rx, res) = run_timer(&sim);
+        drop(event_tx);
+        sim.run_for(Duration::from_millis(1));
+        match *res.lock() {
+            Some(Some(Err(ConsensusError::ChannelErr(_)))) => (),
+            _ => panic!("the timer should exit with a channel error"),
+        }
+        assert_eq!(sim.task_count(), 0);
+    }
 }
@@ -1,10 +1,12 @@
 use std::cmp::{Ord, Ordering, PartialOrd};
+use std::convert::TryFrom;
 
 use bytes::Bytes;
 use derive_more::Display;
+use rlp::DecoderError;
 use serde::{Deserialize, Serialize};
 
-use crate::smr::smr_types::{SMRStatus, Step, TriggerType};
+use crate::smr::smr_types::{Lock, SMRStatus, Step, TriggerType};
 use crate::{Codec, DurationConfig};
 
 /// Address type.
@@ -38,12 +40,14 @@ impl Into<u8> for Role {
     }
 }
 
-impl From<u8> for Role {
-    fn from(s: u8) -> Self {
+impl TryFrom<u8> for Role {
+    type Error = DecoderError;
+
+    fn try_from(s: u8) -> Result<Self, Self::Error> {
         match s {
-            0 => Role::Leader,
-            1 => Role::Replica,
-            _ => panic!("Invalid role!"),
+            0 => Ok(Role::Leader),
+            1 => Ok(Role::Replica),
+            _ => Err(DecoderError::Custom("Invalid role")),
         }
     }
 }
@@ -87,19 +91,22 @@ impl Into<Step> for VoteType {
     }
 }
 
-impl From<u8> for VoteType {
-    fn from(s: u8) -> Self {
+impl TryFrom<u8> for VoteType {
+    type Error = DecoderError;
+
+    fn try_from(s: u8) -> Result<Self, Self::Error> {
         match s {
-            1 => VoteType::Prevote,
-            2 => VoteType::Precommit,
-            _ => panic!("Invalid vote type!"),
+            1 => Ok(VoteType::Prevote),
+            2 => Ok(VoteType::Precommit),
+            _ => Err(DecoderError::Custom("Invalid vote type")),
         }
     }
 }
 
 /// Overlord messages.
 #[allow(clippy::large_enum_variant)]
-#[derive(Clone, Debug, Display, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
+#[serde(bound = "T: Codec")]
 pub enum OverlordMsg<T: Codec> {
     /// Signed proposal message.
     #[display(fmt = "Signed Proposal")]
@@ -132,6 +139,17 @@ impl<T: Codec> OverlordMsg<T> {
             _ => false,
         }
     }
+
+    /// Get the height of a consensus message, `None` for a control message.
+    pub(crate) fn get_height(&self) -> Option<u64> {
+        match self {
+            OverlordMsg::SignedProposal(sp) => Some(sp.proposal.height),
+            OverlordMsg::SignedVote(sv) => Some(sv.get_height()),
+            OverlordMsg::AggregatedVote(av) => Some(av.get_height()),
+            OverlordMsg::SignedChoke(sc) => Some(sc.choke.height),
+            _ => None,
+        }
+    }
 }
 
 /// How does state goto the current round.
@@ -146,17 +164,20 @@ pub enum UpdateFrom {
 }
 
 /// A signed proposal.
-#[derive(Clone, Debug, Display, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
+#[serde(bound = "T: Codec")]
 #[display(fmt = "Signed Proposal {:?}", proposal)]
 pub struct SignedProposal<T: Codec> {
     /// Signature of the proposal.
+    #[serde(with = "super::serde_hex")]
     pub signature: Bytes,
     /// A proposal.
     pub proposal: Proposal<T>,
 }
 
 /// A proposal
-#[derive(Clone, Debug, Display, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
+#[serde(bound = "T: Codec")]
 #[display(fmt = "Proposal height {}, round {}", height, round)]
 pub struct Proposal<T: Codec> {
     /// Height of the proposal.
@@ -164,17 +185,20 @@ pub struct Proposal<T: Codec> {
     /// Round of the proposal.
     pub round: u64,
     /// Proposal content.
+    #[serde(with = "super::serde_codec")]
     pub content: T,
     /// Proposal block hash.
+    #[serde(with = "super::serde_hex")]
     pub block_hash: Hash,
     /// Optional field. If the proposal has a PoLC, this contains the lock round and lock votes.
     pub lock: Option<PoLC>,
     /// Proposer address.
+    #[serde(with = "super::serde_hex")]
     pub proposer: Address,
 }
 
 /// A PoLC.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct PoLC {
     /// Lock round of the proposal.
     pub lock_round: u64,
@@ -183,14 +207,16 @@ pub struct PoLC {
 }
 
 /// A signed vote.
-#[derive(Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq, Hash)]
 #[display(fmt = "Signed vote {:?}", vote)]
 pub struct SignedVote {
     /// Signature of the vote.
+    #[serde(with = "super::serde_hex")]
     pub signature: Bytes,
     /// A vote.
     pub vote: Vote,
     /// Voter address.
+    #[serde(with = "super::serde_hex")]
     pub voter: Address,
 }
 
@@ -288,7 +314,7 @@ impl AggregatedVote {
 }
 
 /// A vote.
-#[derive(Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq, Hash)]
 #[display(fmt = "{:?} vote height {}, round {}", vote_type, height, round)]
 pub struct Vote {
     /// Height of the vote.
@@ -298,37 +324,42 @@ pub struct Vote {
     /// Type of the vote.
     pub vote_type: VoteType,
     /// Block hash of the vote.
+    #[serde(with = "super::serde_hex")]
     pub block_hash: Hash,
 }
 
 /// A commit.
-#[derive(Clone, Debug, Display, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
+#[serde(bound = "T: Codec")]
 #[display(fmt = "Commit height {}", height)]
 pub struct Commit<T: Codec> {
     /// Height of the commit.
     pub height: u64,
     /// Commit content.
+    #[serde(with = "super::serde_codec")]
     pub content: T,
     /// The consensus proof.
     pub proof: Proof,
 }
 
 /// A Proof.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Proof {
     /// Height of the proof.
     pub height: u64,
     /// Round of the proof.
     pub round: u64,
     /// Block hash of the proof.
+    #[serde(with = "super::serde_hex")]
     pub block_hash: Hash,
     /// Aggregated signature of the proof.
     pub signature: AggregatedSignature,
 }
 
-/// A rich status.
-#[derive(Clone, Debug, Display, PartialEq, Eq)]
+/// A rich status. Create it by `Status::new()`, since more fields may be added.
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
 #[display(fmt = "Rich status height {}", height)]
+#[non_exhaustive]
 pub struct Status {
     /// New height.
     pub height: u64,
@@ -338,6 +369,10 @@ pub struct Status {
     pub timer_config: Option<DurationConfig>,
     /// New authority list.
     pub authority_list: Vec<Node>,
+    /// Block hash of the previous height. It is the seed of the proposer elections that are seeded
+    /// by the previous block, such as `PrevHashSeeded`.
+    #[serde(default, with = "super::serde_hex::option")]
+    pub prev_hash: Option<Hash>,
 }
 
 impl Into<SMRStatus> for Status {
@@ -351,6 +386,29 @@ impl Into<SMRStatus> for Status {
 }
 
 impl Status {
+    /// Create a status of the new height without the previous block hash. `None` interval or
+    /// timeout configuration means keeping the current one.
+    pub fn new(
+        height: u64,
+        interval: Option<u64>,
+        timer_config: Option<DurationConfig>,
+        authority_list: Vec<Node>,
+    ) -> Self {
+        Status {
+            height,
+            interval,
+            timer_config,
+            authority_list,
+            prev_hash: None,
+        }
+    }
+
+    /// Set the block hash of the previous height, which seeds the elections such as
+    /// `PrevHashSeeded`.
+    pub fn set_prev_hash(&mut self, prev_hash: Hash) {
+        self.prev_hash = Some(prev_hash);
+    }
+
     pub(crate) fn is_consensus_node(&self, address: &Address) -> bool {
         self.authority_list
             .iter()
@@ -364,8 +422,8 @@ pub struct Node {
     /// Node address.
     #[serde(with = "super::serde_hex")]
     pub address: Address,
-    /// The propose weight of the node. The field is only effective in `features =
-    /// "random_leader"`.
+    /// The propose weight of the node. The field is only effective in weighted proposer
+    /// elections, such as `WeightedRandom` and `PrevHashSeeded`.
     pub propose_weight: u32,
     /// The vote weight of the node.
     pub vote_weight: u32,
@@ -393,8 +451,8 @@ impl Node {
         }
     }
 
-    /// Set a new propose weight of the node. Propose weight is only effective in `features =
-    /// "random_leader"`.
+    /// Set a new propose weight of the node. Propose weight is only effective in weighted proposer
+    /// elections, such as `WeightedRandom` and `PrevHashSeeded`.
     pub fn set_propose_weight(&mut self, propose_weight: u32) {
         self.propose_weight = propose_weight;
     }
@@ -405,6 +463,198 @@ impl Node {
     }
 }
 
+/// A snapshot of the consensus state, which is the response of `OverlordHandler::status()`.
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+#[display(fmt = "Snapshot height {}, round {}, step {}", height, round, step)]
+pub struct Snapshot {
+    /// Current height.
+    pub height: u64,
+    /// Current round.
+    pub round: u64,
+    /// Current step of the state machine.
+    pub step: Step,
+    /// Current lock of the state machine.
+    pub lock: Option<Lock>,
+    /// Leader address of the current round.
+    pub leader_address: Address,
+    /// If self is the leader of the current round.
+    pub is_leader: bool,
+    /// How does state goto the current round.
+    pub update_from_where: UpdateFrom,
+}
+
+/// A consensus event, which can be subscribed by `Overlord::subscribe()` or
+/// `OverlordHandler::subscribe()` to observe the consensus process.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+pub enum ConsensusEvent {
+    /// Goto a new height.
+    #[display(fmt = "New height {}", height)]
+    NewHeight {
+        /// The new height.
+        height: u64,
+    },
+    /// Goto a new round.
+    #[display(fmt = "New round height {}, round {}", height, round)]
+    NewRound {
+        /// Current height.
+        height: u64,
+        /// The new round.
+        round: u64,
+    },
+    /// A proposal of the current round is accepted, which is either proposed by self or received
+    /// from the leader.
+    #[display(
+        fmt = "Proposal height {}, round {}, hash {:?}",
+        height,
+        round,
+        "hex::encode(block_hash)"
+    )]
+    ProposalReceived {
+        /// The height of the proposal.
+        height: u64,
+        /// The round of the proposal.
+        round: u64,
+        /// The block hash of the proposal.
+        block_hash: Hash,
+        /// The proposer address.
+        proposer: Address,
+        /// The lock round of the proposal.
+        lock_round: Option<u64>,
+    },
+    /// A prevote quorum certificate of the current height is formed or received.
+    #[display(fmt = "Prevote QC height {}, round {}", "_0.height", "_0.round")]
+    PrevoteQC(AggregatedVote),
+    /// A precommit quorum certificate of the current height is formed or received.
+    #[display(fmt = "Precommit QC height {}, round {}", "_0.height", "_0.round")]
+    PrecommitQC(AggregatedVote),
+    /// A choke quorum certificate of the current height is formed or received.
+    #[display(fmt = "Choke QC height {}, round {}", "_0.height", "_0.round")]
+    ChokeQC(AggregatedChoke),
+    /// A block is committed.
+    #[display(
+        fmt = "Commit height {}, round {}, hash {:?}",
+        height,
+        round,
+        "hex::encode(block_hash)"
+    )]
+    Commit {
+        /// The height of the committed block.
+        height: u64,
+        /// The round of the committed block.
+        round: u64,
+        /// The hash of the committed block.
+        block_hash: Hash,
+        /// The proof of the committed block.
+        proof: Proof,
+    },
+}
+
+impl ConsensusEvent {
+    pub(crate) fn from_qc(qc: AggregatedVote) -> Self {
+        if qc.is_prevote_qc() {
+            ConsensusEvent::PrevoteQC(qc)
+        } else {
+            ConsensusEvent::PrecommitQC(qc)
+        }
+    }
+}
+
+/// An evidence of equivocation, which consists of two conflicting messages signed by the same
+/// validator in the same height and round. Both signatures have been verified before the evidence
+/// is reported by `Consensus::report_evidence()`.
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
+#[serde(bound = "T: Codec")]
+pub enum Evidence<T: Codec> {
+    /// Two different proposals signed by the same proposer.
+    #[display(fmt = "Double proposal {} and {}", _0, _1)]
+    DoubleProposal(SignedProposal<T>, SignedProposal<T>),
+    /// Two conflicting votes of the same vote type signed by the same voter.
+    #[display(fmt = "Double vote {} and {}", _0, _1)]
+    DoubleVote(SignedVote, SignedVote),
+}
+
+impl<T: Codec> Evidence<T> {
+    /// Get the height of the evidence.
+    pub fn get_height(&self) -> u64 {
+        match self {
+            Evidence::DoubleProposal(first, _) => first.proposal.height,
+            Evidence::DoubleVote(first, _) => first.get_height(),
+        }
+    }
+
+    /// Get the round of the evidence.
+    pub fn get_round(&self) -> u64 {
+        match self {
+            Evidence::DoubleProposal(first, _) => first.proposal.round,
+            Evidence::DoubleVote(first, _) => first.get_round(),
+        }
+    }
+
+    /// Get the address of the byzantine validator.
+    pub fn get_address(&self) -> Address {
+        match self {
+            Evidence::DoubleProposal(first, _) => first.proposal.proposer.clone(),
+            Evidence::DoubleVote(first, _) => first.voter.clone(),
+        }
+    }
+}
+
+/// A misbehaviour of a peer, which is detected from the messages sent by the peer.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Misbehavior {
+    /// The peer sends messages faster than the rate limit.
+    #[display(fmt = "Exceed rate limit")]
+    ExceedRateLimit,
+    /// The peer sends a message with an invalid signature or aggregated signature.
+    #[display(fmt = "Invalid signature")]
+    InvalidSignature,
+    /// The peer sends a message signed by an address which is not in the authority list.
+    #[display(fmt = "Unknown voter")]
+    UnknownVoter,
+    /// The peer sends a message of a height more than one height lower than the current height.
+    #[display(fmt = "Outdated message")]
+    OutdatedMessage,
+    /// The peer sends a malformed message or a message that violates the protocol, such as a
+    /// proposal from a wrong proposer.
+    #[display(fmt = "Invalid message")]
+    InvalidMessage,
+}
+
+impl Misbehavior {
+    /// The score of a misbehaviour added to the peer each time.
+    pub fn weight(self) -> u64 {
+        match self {
+            Misbehavior::ExceedRateLimit | Misbehavior::OutdatedMessage => 1,
+            Misbehavior::InvalidSignature
+            | Misbehavior::UnknownVoter
+            | Misbehavior::InvalidMessage => 10,
+        }
+    }
+}
+
+/// A penalty of a peer. The peer is the sender of the messages, which is carried in the `Context`
+/// by `with_peer()`, rather than the signer of the messages.
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+#[display(
+    fmt = "Peer {} {} {} times, score {}",
+    "hex::encode(peer)",
+    misbehavior,
+    count,
+    score
+)]
+pub struct PeerPenalty {
+    /// The address of the peer.
+    pub peer: Address,
+    /// The misbehaviour of the peer.
+    pub misbehavior: Misbehavior,
+    /// The times of the misbehaviour made by the peer.
+    pub count: u64,
+    /// The total score of all the misbehaviours made by the peer.
+    pub score: u64,
+}
+
 /// A verify response.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct VerifyResp {
@@ -416,6 +666,8 @@ pub(crate) struct VerifyResp {
     pub(crate) block_hash: Hash,
     /// The block is pass or not.
     pub(crate) is_pass: bool,
+    /// The reason why the block does not pass.
+    pub(crate) reason: Option<String>,
 }
 
 /// An aggregated choke.
@@ -425,20 +677,11 @@ pub struct AggregatedChoke {
     pub height: u64,
     /// The round of the aggregated choke.
     pub round: u64,
-    /// The aggregated signature of the aggregated choke.
-    #[serde(with = "super::serde_hex")]
-    pub signature: Signature,
-    /// The voters of the aggregated choke.
-    #[serde(with = "super::serde_multi_hex")]
-    pub voters: Vec<Address>,
+    /// The aggregated signature of the aggregated choke with the voters' address bitmap.
+    pub signature: AggregatedSignature,
 }
 
-#[allow(clippy::len_without_is_empty)]
 impl AggregatedChoke {
-    pub(crate) fn len(&self) -> usize {
-        self.voters.len()
-    }
-
     pub(crate) fn to_hash(&self) -> HashChoke {
         HashChoke {
             height: self.height,
@@ -448,18 +691,20 @@ impl AggregatedChoke {
 }
 
 /// A signed choke.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
 pub struct SignedChoke {
     /// The signature of the choke.
+    #[serde(with = "super::serde_hex")]
     pub signature: Signature,
     /// The choke message.
     pub choke: Choke,
     /// The choke address.
+    #[serde(with = "super::serde_hex")]
     pub address: Address,
 }
 
 /// A choke.
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
 pub struct Choke {
     /// The height of the choke.
     pub height: u64,
@@ -503,6 +748,7 @@ mod test {
             interval:       None,
             timer_config:   None,
             authority_list: vec![mock_node(), mock_node()],
+            prev_hash:      None,
         }
     }
 
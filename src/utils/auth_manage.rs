@@ -1,13 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use bit_vec::BitVec;
+use bytes::Bytes;
 use derive_more::Display;
-use prime_tools::get_primes_less_than_x;
 
 use crate::error::ConsensusError;
-use crate::types::{Address, Node};
-use crate::utils::rand_proposer::get_random_proposer_index;
-use crate::ConsensusResult;
+use crate::types::{Address, Node, Proof, Vote, VoteType};
+use crate::utils::domain::{signing_payload, MsgTag};
+use crate::{ConsensusResult, Crypto, ProposerElection};
 
 /// Authority manage is an extensional data structure of authority list which means
 /// `Vec<Node>`. It transforms the information in `Node` struct into a more suitable data structure
@@ -16,22 +16,20 @@ use crate::ConsensusResult;
 #[display(fmt = "Authority List {:?}", address)]
 #[derive(Clone, Debug, Display, PartialEq, Eq)]
 pub struct AuthorityManage {
-    address:            Vec<Address>,
-    propose_weights:    Vec<u64>,
-    vote_weight_map:    HashMap<Address, u32>,
-    propose_weight_sum: u64,
-    vote_weight_sum:    u64,
+    nodes:           Vec<Node>,
+    address:         Vec<Address>,
+    vote_weight_map: HashMap<Address, u32>,
+    vote_weight_sum: u64,
 }
 
 impl AuthorityManage {
     /// Create a new height authority manage.
     pub fn new() -> Self {
         AuthorityManage {
-            address:            Vec::new(),
-            propose_weights:    Vec::new(),
-            vote_weight_map:    HashMap::new(),
-            propose_weight_sum: 0u64,
-            vote_weight_sum:    0u64,
+            nodes:           Vec::new(),
+            address:         Vec::new(),
+            vote_weight_map: HashMap::new(),
+            vote_weight_sum: 0u64,
         }
     }
 
@@ -41,46 +39,43 @@ impl AuthorityManage {
         authority_list.sort();
 
         for node in authority_list.iter_mut() {
-            let propose_weight = u64::from(node.propose_weight);
             let vote_weight = node.vote_weight;
 
             self.address.push(node.address.clone());
-            self.propose_weights.push(propose_weight);
             self.vote_weight_map
                 .insert(node.address.clone(), vote_weight);
-            self.propose_weight_sum += propose_weight;
             self.vote_weight_sum += u64::from(vote_weight);
         }
+        self.nodes = authority_list.clone();
+    }
+
+    /// Get the authority list sorted by address.
+    pub fn get_authority_list(&self) -> &[Node] {
+        &self.nodes
     }
 
     /// Get a vote weight of the node.
     pub fn get_vote_weight(&self, addr: &Address) -> ConsensusResult<&u32> {
         self.vote_weight_map
             .get(addr)
-            .ok_or_else(|| ConsensusError::InvalidAddress)
+            .ok_or_else(|| ConsensusError::InvalidAddress(addr.to_owned()))
     }
 
-    /// Get the proposer address by a given seed.
-    pub fn get_proposer(&self, height: u64, round: u64) -> ConsensusResult<Address> {
-        let index = if cfg!(features = "random_leader") {
-            get_random_proposer_index(
-                height + round,
-                &self.propose_weights,
-                self.propose_weight_sum,
-            )
-        } else {
-            let len = self.address.len();
-            let prime_num = *get_primes_less_than_x(len as u32).last().unwrap_or(&1) as u64;
-            let res = (height * prime_num + round) % (len as u64);
-            res as usize
-        };
-
-        if let Some(addr) = self.address.get(index) {
-            return Ok(addr.to_owned());
+    /// Get the proposer address of the given height and round by the election.
+    pub fn get_proposer(
+        &self,
+        election: &dyn ProposerElection,
+        height: u64,
+        round: u64,
+    ) -> ConsensusResult<Address> {
+        let proposer = election.elect(height, round, &self.nodes)?;
+        if !self.contains(&proposer) {
+            return Err(ConsensusError::Other(format!(
+                "Elected proposer {:?} is not in the authority list",
+                hex::encode(proposer)
+            )));
         }
-        Err(ConsensusError::Other(
-            "The address list mismatch propose weight list".to_string(),
-        ))
+        Ok(proposer)
     }
 
     /// Calculate whether the sum of vote weights from bitmap is above 2/3.
@@ -115,6 +110,19 @@ impl AuthorityManage {
         Ok(voters)
     }
 
+    /// Get the address bitmap of the given voters, the voters that are not in the current
+    /// authority list will be ignored.
+    pub fn get_bitmap(&self, voters: &[Address]) -> Bytes {
+        let set = voters.iter().collect::<HashSet<_>>();
+        let mut bitmap = BitVec::from_elem(self.address.len(), false);
+        for (index, addr) in self.address.iter().enumerate() {
+            if set.contains(addr) {
+                bitmap.set(index, true);
+            }
+        }
+        Bytes::from(bitmap.to_bytes())
+    }
+
     /// If the given address is in the current authority list.
     pub fn contains(&self, address: &Address) -> bool {
         self.address.contains(address)
@@ -127,21 +135,11 @@ impl AuthorityManage {
 
     /// Clear the HeightAuthorityManage, removing all values.
     pub fn flush(&mut self) {
+        self.nodes.clear();
         self.address.clear();
-        self.propose_weights.clear();
         self.vote_weight_map.clear();
-        self.propose_weight_sum = 0;
         self.vote_weight_sum = 0;
     }
-
-    /// Get the length of the current authority list.
-    pub fn len(&self) -> usize {
-        self.address.len()
-    }
-
-    pub fn get_addres_ref(&self) -> &Vec<Address> {
-        &self.address
-    }
 }
 
 /// give the validators list and bitmap, returns the activated validators, the authority_list MUST
@@ -161,19 +159,135 @@ pub fn extract_voters(
     Ok(voters)
 }
 
+/// Verify a proof exactly as consensus verifies a precommit quorum certificate. Rebuild the
+/// precommit vote of the proof and hash its signing payload of the chain id, check the sum of the
+/// vote weights in the bitmap is above 2/3 of the authority list, then verify the aggregated
+/// signature. The authority list should be the one of the proof height.
+pub fn verify_proof<C: Crypto>(
+    proof: &Proof,
+    authority_list: &[Node],
+    chain_id: &[u8],
+    crypto: &C,
+) -> ConsensusResult<()> {
+    if proof.block_hash.is_empty() {
+        return Err(ConsensusError::AggregatedSignatureErr {
+            height: proof.height,
+            round:  proof.round,
+            reason: "Proof is for an empty block hash".to_string(),
+        });
+    }
+
+    let mut authority = AuthorityManage::new();
+    authority.update(&mut authority_list.to_vec());
+
+    let bitmap = &proof.signature.address_bitmap;
+    if !authority.is_above_threshold(bitmap)? {
+        return Err(ConsensusError::AggregatedSignatureErr {
+            height: proof.height,
+            round:  proof.round,
+            reason: "Proof is not above threshold".to_string(),
+        });
+    }
+
+    let mut voters = authority.get_voters(bitmap)?;
+    voters.sort();
+
+    let vote = Vote {
+        height:     proof.height,
+        round:      proof.round,
+        vote_type:  VoteType::Precommit,
+        block_hash: proof.block_hash.clone(),
+    };
+
+    crypto
+        .verify_aggregated_signature(
+            proof.signature.signature.clone(),
+            crypto.hash(signing_payload(chain_id, MsgTag::Vote, &vote)),
+            voters,
+        )
+        .map_err(|source| ConsensusError::InvalidSignature {
+            height: proof.height,
+            round: proof.round,
+            address: None,
+            source,
+        })
+}
+
 #[cfg(test)]
 mod test {
     extern crate test;
 
+    use std::error::Error;
+
     use bit_vec::BitVec;
-    use bytes::Bytes;
+    use bytes::{Bytes, BytesMut};
     use rand::random;
     use test::Bencher;
 
+    use crate::election::RoundRobin;
     use crate::error::ConsensusError;
-    use crate::extract_voters;
-    use crate::types::{Address, Node};
+    use crate::types::{
+        Address, AggregatedSignature, Hash, Node, Proof, Signature, Vote, VoteType,
+    };
     use crate::utils::auth_manage::AuthorityManage;
+    use crate::utils::domain::{signing_payload, MsgTag};
+    use crate::{extract_voters, verify_proof, Crypto};
+
+    const CHAIN_ID: &[u8] = b"main";
+
+    /// A mock crypto whose aggregated signature is the message hash followed by the sorted voters.
+    struct MockCrypto;
+
+    impl MockCrypto {
+        fn aggregate(hash: &Hash, voters: &[Address]) -> Signature {
+            let mut res = BytesMut::from(hash.as_ref());
+            for voter in voters.iter() {
+                res.extend_from_slice(voter);
+            }
+            res.freeze()
+        }
+    }
+
+    impl Crypto for MockCrypto {
+        fn hash(&self, msg: Bytes) -> Hash {
+            msg
+        }
+
+        fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+            Ok(hash)
+        }
+
+        fn aggregate_signatures(
+            &self,
+            signatures: Vec<Signature>,
+            _voters: Vec<Address>,
+        ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+            Ok(signatures.concat().into())
+        }
+
+        fn verify_signature(
+            &self,
+            _signature: Signature,
+            _hash: Hash,
+            _voter: Address,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Ok(())
+        }
+
+        fn verify_aggregated_signature(
+            &self,
+            aggregate_signature: Signature,
+            msg_hash: Hash,
+            voters: Vec<Address>,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            if aggregate_signature != MockCrypto::aggregate(&msg_hash, &voters) {
+                return Err(Box::new(ConsensusError::Other(
+                    "Invalid aggregated signature".to_string(),
+                )));
+            }
+            Ok(())
+        }
+    }
 
     fn gen_address() -> Address {
         Address::from((0..32).map(|_| random::<u8>()).collect::<Vec<_>>())
@@ -215,7 +329,7 @@ mod test {
         for node in authority_list.iter() {
             assert_eq!(
                 authority_manage.get_vote_weight(&node.address),
-                Err(ConsensusError::InvalidAddress)
+                Err(ConsensusError::InvalidAddress(node.address.clone()))
             );
         }
 
@@ -303,6 +417,25 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_get_bitmap() {
+        let mut authority_list = gen_auth_list(10);
+        let mut authority = AuthorityManage::new();
+        authority.update(&mut authority_list);
+
+        let mut voters = vec![
+            authority_list[7].address.clone(),
+            authority_list[2].address.clone(),
+            gen_address(),
+        ];
+        let bitmap = authority.get_bitmap(&voters);
+        assert_eq!(bitmap, Bytes::from(gen_bitmap(10, vec![2, 7]).to_bytes()));
+
+        voters.pop();
+        voters.sort();
+        assert_eq!(authority.get_voters(&bitmap).unwrap(), voters);
+    }
+
     #[test]
     fn test_get_voters() {
         let auth_list = (0..4).map(|_| gen_address()).collect::<Vec<_>>();
@@ -332,27 +465,27 @@ mod test {
         authority.update(&mut authority_list);
 
         assert_eq!(
-            authority.get_proposer(1, 0).unwrap(),
+            authority.get_proposer(&RoundRobin, 1, 0).unwrap(),
             authority_list[3].address
         );
         assert_eq!(
-            authority.get_proposer(1, 1).unwrap(),
+            authority.get_proposer(&RoundRobin, 1, 1).unwrap(),
             authority_list[0].address
         );
         assert_eq!(
-            authority.get_proposer(2, 0).unwrap(),
+            authority.get_proposer(&RoundRobin, 2, 0).unwrap(),
             authority_list[2].address
         );
         assert_eq!(
-            authority.get_proposer(2, 2).unwrap(),
+            authority.get_proposer(&RoundRobin, 2, 2).unwrap(),
             authority_list[0].address
         );
         assert_eq!(
-            authority.get_proposer(3, 0).unwrap(),
+            authority.get_proposer(&RoundRobin, 3, 0).unwrap(),
             authority_list[1].address
         );
         assert_eq!(
-            authority.get_proposer(3, 1).unwrap(),
+            authority.get_proposer(&RoundRobin, 3, 1).unwrap(),
             authority_list[2].address
         );
     }
@@ -375,6 +508,66 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_verify_proof() {
+        let mut authority_list = (0..4)
+            .map(|_| gen_node(gen_address(), 1u32, 1u32))
+            .collect::<Vec<_>>();
+        authority_list.sort();
+        let voters = authority_list
+            .iter()
+            .take(3)
+            .map(|node| node.address.clone())
+            .collect::<Vec<_>>();
+
+        let mut proof = Proof {
+            height:     random::<u64>(),
+            round:      random::<u64>(),
+            block_hash: gen_address(),
+            signature:  AggregatedSignature {
+                signature:      Signature::new(),
+                address_bitmap: Bytes::from(gen_bitmap(4, vec![0, 1, 2]).to_bytes()),
+            },
+        };
+        let vote = Vote {
+            height:     proof.height,
+            round:      proof.round,
+            vote_type:  VoteType::Precommit,
+            block_hash: proof.block_hash.clone(),
+        };
+        proof.signature.signature =
+            MockCrypto::aggregate(&signing_payload(CHAIN_ID, MsgTag::Vote, &vote), &voters);
+        assert!(verify_proof(&proof, &authority_list, CHAIN_ID, &MockCrypto).is_ok());
+
+        // The authority list order does not matter.
+        let mut reversed = authority_list.clone();
+        reversed.reverse();
+        assert!(verify_proof(&proof, &reversed, CHAIN_ID, &MockCrypto).is_ok());
+
+        // Below threshold.
+        let mut below = proof.clone();
+        below.signature.address_bitmap = Bytes::from(gen_bitmap(4, vec![0, 1]).to_bytes());
+        assert!(verify_proof(&below, &authority_list, CHAIN_ID, &MockCrypto).is_err());
+
+        // Bitmap mismatch the aggregated signature.
+        let mut mismatch = proof.clone();
+        mismatch.signature.address_bitmap = Bytes::from(gen_bitmap(4, vec![1, 2, 3]).to_bytes());
+        assert!(verify_proof(&mismatch, &authority_list, CHAIN_ID, &MockCrypto).is_err());
+
+        // Tampered block hash.
+        let mut tampered = proof.clone();
+        tampered.block_hash = gen_address();
+        assert!(verify_proof(&tampered, &authority_list, CHAIN_ID, &MockCrypto).is_err());
+
+        // Another chain.
+        assert!(verify_proof(&proof, &authority_list, b"test", &MockCrypto).is_err());
+
+        // Empty block hash.
+        let mut empty = proof;
+        empty.block_hash = Hash::new();
+        assert!(verify_proof(&empty, &authority_list, CHAIN_ID, &MockCrypto).is_err());
+    }
+
     #[bench]
     fn bench_update(b: &mut Bencher) {
         let mut auth_list = gen_auth_list(10);
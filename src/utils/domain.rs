@@ -0,0 +1,66 @@
+use bytes::Bytes;
+use derive_more::Display;
+use rlp::{Encodable, RlpStream};
+
+/// The type tag of a signed consensus message. The tag is mixed into the signed payload, so that
+/// a signature of one type of message can not be replayed as another type.
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum MsgTag {
+    /// A proposal.
+    #[display(fmt = "Proposal")]
+    Proposal,
+    /// A prevote or precommit vote.
+    #[display(fmt = "Vote")]
+    Vote,
+    /// A choke.
+    #[display(fmt = "Choke")]
+    Choke,
+}
+
+impl Into<u8> for MsgTag {
+    fn into(self) -> u8 {
+        match self {
+            MsgTag::Proposal => 0,
+            MsgTag::Vote => 1,
+            MsgTag::Choke => 2,
+        }
+    }
+}
+
+/// Build the payload of a consensus message to sign, which is the RLP encoding of
+/// `[chain id, tag, message]`. The hash of the payload is signed and verified, so that a
+/// signature can not be replayed on another chain which shares the same validator keys.
+pub fn signing_payload<M: Encodable>(chain_id: &[u8], tag: MsgTag, msg: &M) -> Bytes {
+    let mut s = RlpStream::new_list(3);
+    s.append(&chain_id.to_vec())
+        .append::<u8>(&tag.into())
+        .append(msg);
+    Bytes::from(s.out())
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+
+    use crate::types::{Vote, VoteType};
+
+    use super::{signing_payload, MsgTag};
+
+    #[test]
+    fn test_signing_payload() {
+        let vote = Vote {
+            height:     1,
+            round:      0,
+            vote_type:  VoteType::Prevote,
+            block_hash: Bytes::from(vec![1u8; 4]),
+        };
+        let payload = signing_payload(b"main", MsgTag::Vote, &vote);
+        assert_eq!(payload, signing_payload(b"main", MsgTag::Vote, &vote));
+
+        // Another chain or another type of message has a different payload.
+        assert_ne!(payload, signing_payload(b"test", MsgTag::Vote, &vote));
+        assert_ne!(payload, signing_payload(b"", MsgTag::Vote, &vote));
+        assert_ne!(payload, signing_payload(b"main", MsgTag::Choke, &vote));
+        assert_ne!(payload.as_ref(), rlp::encode(&vote).as_slice());
+    }
+}
@@ -0,0 +1,120 @@
+use std::mem;
+use std::sync::Arc;
+
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use log::warn;
+use parking_lot::Mutex;
+
+use crate::types::ConsensusEvent;
+
+/// The number of events buffered for each subscriber.
+pub const EVENT_BUFFER_SIZE: usize = 1024;
+
+/// A hub to publish consensus events to all subscribers. Each subscriber has a bounded buffer of
+/// `EVENT_BUFFER_SIZE` events. Subscribers whose receiver has been dropped, or whose buffer is
+/// full since they lag behind, are removed on the next publication, and the stream of a removed
+/// subscriber ends.
+#[derive(Clone, Debug, Default)]
+pub struct EventHub {
+    subscribers: Arc<Mutex<Vec<Sender<ConsensusEvent>>>>,
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        EventHub::default()
+    }
+
+    /// Subscribe consensus events, all events published after this will be sent to the receiver.
+    pub fn subscribe(&self) -> Receiver<ConsensusEvent> {
+        let (tx, rx) = channel(EVENT_BUFFER_SIZE);
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
+    /// Publish a consensus event to all subscribers.
+    pub fn publish(&self, event: ConsensusEvent) {
+        let mut subscribers = self.subscribers.lock();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        // Do not clone the senders to send, since each sender clone has a slot of its own.
+        *subscribers = mem::replace(&mut *subscribers, Vec::new())
+            .into_iter()
+            .filter_map(|mut tx| match tx.try_send(event.clone()) {
+                Ok(()) => Some(tx),
+                Err(err) => {
+                    if err.is_full() {
+                        warn!("Overlord: event hub drop a lagging subscriber");
+                    }
+                    None
+                }
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::stream::StreamExt;
+
+    use crate::types::ConsensusEvent;
+
+    use super::{EventHub, EVENT_BUFFER_SIZE};
+
+    #[test]
+    fn test_publish() {
+        let hub = EventHub::new();
+        let mut rx_1 = hub.subscribe();
+        let rx_2 = hub.subscribe();
+        drop(rx_2);
+
+        hub.publish(ConsensusEvent::NewHeight { height: 1 });
+        assert_eq!(hub.subscribers.lock().len(), 1);
+
+        let mut rx_3 = hub.subscribe();
+        hub.publish(ConsensusEvent::NewRound {
+            height: 1,
+            round:  1,
+        });
+
+        let res = futures::executor::block_on(async {
+            (rx_1.next().await, rx_1.next().await, rx_3.next().await)
+        });
+        assert_eq!(res.0, Some(ConsensusEvent::NewHeight { height: 1 }));
+        assert_eq!(
+            res.1,
+            Some(ConsensusEvent::NewRound {
+                height: 1,
+                round:  1,
+            })
+        );
+        assert_eq!(
+            res.2,
+            Some(ConsensusEvent::NewRound {
+                height: 1,
+                round:  1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_lagging_subscriber() {
+        let hub = EventHub::new();
+        let mut rx = hub.subscribe();
+        for height in 0..EVENT_BUFFER_SIZE as u64 + 2 {
+            hub.publish(ConsensusEvent::NewHeight { height });
+        }
+        assert!(hub.subscribers.lock().is_empty());
+
+        // The buffered events are still received, then the stream ends.
+        let count = futures::executor::block_on(async {
+            let mut count = 0;
+            while rx.next().await.is_some() {
+                count += 1;
+            }
+            count
+        });
+        assert!(count >= EVENT_BUFFER_SIZE);
+    }
+}
@@ -1,6 +1,16 @@
 ///
 pub mod auth_manage;
 ///
-mod rand_proposer;
+pub mod domain;
+///
+pub mod event_hub;
+///
+pub mod msg_queue;
+///
+pub mod peer_guard;
+///
+pub(crate) mod rand_proposer;
 ///
 pub mod timer_config;
+///
+pub mod verify_cache;
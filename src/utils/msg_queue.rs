@@ -0,0 +1,378 @@
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll, Waker};
+
+use creep::Context;
+use futures::stream::{FusedStream, Stream};
+use log::debug;
+use parking_lot::Mutex;
+
+use crate::overlord::TrySendError;
+use crate::types::OverlordMsg;
+use crate::Codec;
+
+const PRIORITY_NUM: usize = 4;
+const CONTROL_PRIORITY: usize = 0;
+
+type Item<T> = (Context, OverlordMsg<T>);
+
+/// A bounded inbound message queue shared by the overlord handlers and the state. Messages are
+/// popped by priority: control messages first, then QCs, proposals, and individual votes last.
+/// When the queue is full, a message evicts the oldest message of a lower priority, or it is
+/// rejected as full. Control messages are never rejected as full, they go beyond the capacity if
+/// there is nothing to evict. Messages of heights lower than the current height are stale, they are
+/// dropped when the height goes forward.
+#[derive(Clone)]
+pub struct MsgQueue<T: Codec> {
+    inner: Arc<Mutex<QueueInner<T>>>,
+}
+
+struct QueueInner<T: Codec> {
+    capacity: usize,
+    height:   u64,
+    queues:   Vec<VecDeque<Item<T>>>,
+    closed:   bool,
+    waker:    Option<Waker>,
+}
+
+impl<T: Codec> Debug for MsgQueue<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let inner = self.inner.lock();
+        f.debug_struct("MsgQueue")
+            .field("capacity", &inner.capacity)
+            .field("height", &inner.height)
+            .field("len", &inner.len())
+            .field("closed", &inner.closed)
+            .finish()
+    }
+}
+
+impl<T: Codec> MsgQueue<T> {
+    /// Create an empty queue of the capacity.
+    pub fn new(capacity: usize) -> Self {
+        MsgQueue {
+            inner: Arc::new(Mutex::new(QueueInner {
+                capacity,
+                height: 0,
+                queues: (0..PRIORITY_NUM).map(|_| VecDeque::new()).collect(),
+                closed: false,
+                waker: None,
+            })),
+        }
+    }
+
+    /// Set the capacity of the queue. If the queue holds more messages than the new capacity, no
+    /// message is dropped, but new messages are not accepted until the queue is drained.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.inner.lock().capacity = capacity;
+    }
+
+    /// The number of queued messages.
+    pub fn len(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    /// Whether the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The current height of the queue.
+    pub fn height(&self) -> u64 {
+        self.inner.lock().height
+    }
+
+    /// Push a message into the queue without blocking. A stale message is dropped directly.
+    pub fn try_push(&self, ctx: Context, msg: OverlordMsg<T>) -> Result<(), TrySendError<T>> {
+        let mut inner = self.inner.lock();
+        if inner.closed {
+            return Err(TrySendError::Closed(msg));
+        }
+
+        if inner.is_stale(&msg) {
+            debug!("Overlord: message queue drop a stale {}", msg);
+            return Ok(());
+        }
+
+        let priority = priority(&msg);
+        if inner.len() >= inner.capacity && !inner.evict(priority) && priority != CONTROL_PRIORITY {
+            return Err(TrySendError::Full(msg));
+        }
+
+        inner.queues[priority].push_back((ctx, msg));
+        let waker = inner.waker.take();
+        drop(inner);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Update the current height and drop the stale messages.
+    pub fn set_height(&self, height: u64) {
+        let mut inner = self.inner.lock();
+        if height <= inner.height {
+            return;
+        }
+
+        inner.height = height;
+        inner.queues.iter_mut().for_each(|queue| {
+            queue.retain(|(_, msg)| msg.get_height().map_or(true, |h| h >= height))
+        });
+    }
+
+    /// Drop all the messages in the queue and reset the height, so that the next run can start
+    /// from any height.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.height = 0;
+        inner.queues.iter_mut().for_each(VecDeque::clear);
+    }
+
+    /// Close the queue, the messages in the queue can still be popped.
+    pub fn close(&self) {
+        let waker = {
+            let mut inner = self.inner.lock();
+            inner.closed = true;
+            inner.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: Codec> Stream for MsgQueue<T> {
+    type Item = Item<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Option<Self::Item>> {
+        let mut inner = self.inner.lock();
+        if let Some(item) = inner.queues.iter_mut().find_map(VecDeque::pop_front) {
+            return Poll::Ready(Some(item));
+        }
+
+        if inner.closed {
+            return Poll::Ready(None);
+        }
+
+        inner.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T: Codec> FusedStream for MsgQueue<T> {
+    fn is_terminated(&self) -> bool {
+        let inner = self.inner.lock();
+        inner.closed && inner.len() == 0
+    }
+}
+
+impl<T: Codec> QueueInner<T> {
+    fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    fn is_stale(&self, msg: &OverlordMsg<T>) -> bool {
+        msg.get_height().map_or(false, |h| h < self.height)
+    }
+
+    /// Evict the oldest message of the lowest priority which is lower than the given priority.
+    fn evict(&mut self, priority: usize) -> bool {
+        for queue in self.queues.iter_mut().skip(priority + 1).rev() {
+            if let Some((_, msg)) = queue.pop_front() {
+                debug!("Overlord: message queue is full, evict {}", msg);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// The priority of a message, a smaller number means a higher priority.
+fn priority<T: Codec>(msg: &OverlordMsg<T>) -> usize {
+    match msg {
+        OverlordMsg::RichStatus(_) | OverlordMsg::Stop => CONTROL_PRIORITY,
+        OverlordMsg::AggregatedVote(_) => 1,
+        OverlordMsg::SignedProposal(_) => 2,
+        OverlordMsg::SignedVote(_) | OverlordMsg::SignedChoke(_) => 3,
+
+        #[cfg(test)]
+        OverlordMsg::Commit(_) => CONTROL_PRIORITY,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+
+    use bytes::Bytes;
+    use creep::Context;
+    use futures::executor::block_on;
+    use futures::stream::StreamExt;
+
+    use crate::overlord::TrySendError;
+    use crate::types::{
+        AggregatedSignature, AggregatedVote, OverlordMsg, SignedVote, Status, Vote, VoteType,
+    };
+    use crate::Codec;
+
+    use super::MsgQueue;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Pill;
+
+    impl Codec for Pill {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+            Ok(Bytes::new())
+        }
+
+        fn decode(_data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
+            Ok(Pill)
+        }
+    }
+
+    fn gen_vote(height: u64) -> OverlordMsg<Pill> {
+        OverlordMsg::SignedVote(SignedVote {
+            signature: Bytes::from(vec![0u8]),
+            vote:      Vote {
+                height,
+                round: 0,
+                vote_type: VoteType::Prevote,
+                block_hash: Bytes::from(vec![1u8]),
+            },
+            voter:     Bytes::from(vec![2u8]),
+        })
+    }
+
+    fn gen_qc(height: u64) -> OverlordMsg<Pill> {
+        OverlordMsg::AggregatedVote(AggregatedVote {
+            signature: AggregatedSignature {
+                signature:      Bytes::from(vec![0u8]),
+                address_bitmap: Bytes::from(vec![0u8]),
+            },
+            vote_type: VoteType::Precommit,
+            height,
+            round: 0,
+            block_hash: Bytes::from(vec![1u8]),
+            leader: Bytes::from(vec![2u8]),
+        })
+    }
+
+    fn gen_status(height: u64) -> OverlordMsg<Pill> {
+        OverlordMsg::RichStatus(Status {
+            height,
+            interval: None,
+            timer_config: None,
+            authority_list: vec![],
+            prev_hash: None,
+        })
+    }
+
+    fn pop_all(queue: &mut MsgQueue<Pill>) -> Vec<OverlordMsg<Pill>> {
+        let mut res = Vec::new();
+        while !queue.is_empty() {
+            res.push(block_on(queue.next()).unwrap().1);
+        }
+        res
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut queue = MsgQueue::new(10);
+        queue.try_push(Context::new(), gen_vote(1)).unwrap();
+        queue.try_push(Context::new(), gen_qc(1)).unwrap();
+        queue.try_push(Context::new(), gen_vote(2)).unwrap();
+        queue.try_push(Context::new(), OverlordMsg::Stop).unwrap();
+        queue.try_push(Context::new(), gen_status(2)).unwrap();
+
+        assert_eq!(pop_all(&mut queue), vec![
+            OverlordMsg::Stop,
+            gen_status(2),
+            gen_qc(1),
+            gen_vote(1),
+            gen_vote(2)
+        ]);
+    }
+
+    #[test]
+    fn test_full() {
+        let mut queue = MsgQueue::new(2);
+        queue.try_push(Context::new(), gen_vote(1)).unwrap();
+        queue.try_push(Context::new(), gen_vote(2)).unwrap();
+
+        // A vote can not evict a vote.
+        match queue.try_push(Context::new(), gen_vote(3)) {
+            Err(TrySendError::Full(msg)) => assert_eq!(msg, gen_vote(3)),
+            _ => panic!("the queue should be full"),
+        }
+
+        // A QC evicts the oldest vote, and a control message evicts the other vote.
+        queue.try_push(Context::new(), gen_qc(1)).unwrap();
+        queue.try_push(Context::new(), OverlordMsg::Stop).unwrap();
+        match queue.try_push(Context::new(), gen_qc(2)) {
+            Err(TrySendError::Full(_)) => (),
+            _ => panic!("the queue should be full"),
+        }
+        assert_eq!(pop_all(&mut queue), vec![OverlordMsg::Stop, gen_qc(1)]);
+
+        queue.set_capacity(3);
+        queue.try_push(Context::new(), gen_vote(1)).unwrap();
+        queue.try_push(Context::new(), gen_vote(2)).unwrap();
+        queue.try_push(Context::new(), gen_vote(3)).unwrap();
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn test_control_beyond_capacity() {
+        let mut queue = MsgQueue::new(0);
+        queue.try_push(Context::new(), OverlordMsg::Stop).unwrap();
+        assert_eq!(pop_all(&mut queue), vec![OverlordMsg::Stop]);
+
+        let mut queue = MsgQueue::new(1);
+        queue.try_push(Context::new(), gen_status(1)).unwrap();
+        queue.try_push(Context::new(), OverlordMsg::Stop).unwrap();
+        match queue.try_push(Context::new(), gen_vote(1)) {
+            Err(TrySendError::Full(_)) => (),
+            _ => panic!("the queue should be full"),
+        }
+        assert_eq!(pop_all(&mut queue), vec![gen_status(1), OverlordMsg::Stop]);
+
+        // A queue full of votes evicts a vote for the control message.
+        let mut queue = MsgQueue::new(2);
+        queue.try_push(Context::new(), gen_vote(1)).unwrap();
+        queue.try_push(Context::new(), gen_vote(2)).unwrap();
+        queue.try_push(Context::new(), OverlordMsg::Stop).unwrap();
+        assert_eq!(pop_all(&mut queue), vec![OverlordMsg::Stop, gen_vote(2)]);
+    }
+
+    #[test]
+    fn test_stale() {
+        let mut queue = MsgQueue::new(10);
+        queue.try_push(Context::new(), gen_vote(1)).unwrap();
+        queue.try_push(Context::new(), gen_qc(1)).unwrap();
+        queue.try_push(Context::new(), gen_vote(2)).unwrap();
+        queue.try_push(Context::new(), gen_status(1)).unwrap();
+
+        queue.set_height(2);
+        queue.try_push(Context::new(), gen_vote(1)).unwrap();
+        assert_eq!(pop_all(&mut queue), vec![gen_status(1), gen_vote(2)]);
+    }
+
+    #[test]
+    fn test_close() {
+        let mut queue = MsgQueue::new(10);
+        queue.try_push(Context::new(), gen_vote(1)).unwrap();
+        queue.close();
+        match queue.try_push(Context::new(), gen_vote(1)) {
+            Err(TrySendError::Closed(_)) => (),
+            _ => panic!("the queue should be closed"),
+        }
+
+        assert_eq!(block_on(queue.next()).unwrap().1, gen_vote(1));
+        assert!(block_on(queue.next()).is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::mem;
+use std::sync::Arc;
+use std::time::Instant;
+
+use creep::Context;
+use log::debug;
+use parking_lot::Mutex;
+
+use crate::error::{ConsensusError, ErrorKind};
+use crate::types::{Address, Misbehavior, PeerPenalty};
+use crate::{Clock, RateLimit};
+
+const PEER_KEY: &str = "overlord_peer";
+
+/// Attach the address of the peer who sends a message to the context. The network layer should
+/// send messages to the overlord handler with the context, so that the peer can be rate limited
+/// and punished.
+pub fn with_peer(ctx: &Context, peer: Address) -> Context {
+    ctx.with_value::<Address>(PEER_KEY, peer)
+}
+
+/// Get the address of the peer from the context.
+pub fn get_peer(ctx: &Context) -> Option<Address> {
+    ctx.get::<Address>(PEER_KEY).cloned()
+}
+
+/// Get the misbehaviour indicated by an error of handling a message.
+pub fn misbehavior_of(err: &ConsensusError) -> Option<Misbehavior> {
+    match err.kind() {
+        ErrorKind::InvalidSignature => Some(Misbehavior::InvalidSignature),
+        ErrorKind::UnknownVoter => Some(Misbehavior::UnknownVoter),
+        ErrorKind::InvalidMessage => Some(Misbehavior::InvalidMessage),
+        _ => None,
+    }
+}
+
+/// A peer-aware guard of the inbound messages shared by the overlord handlers and the state. It
+/// rate limits the messages of each peer and scores the misbehaviours of each peer. The penalties
+/// are coalesced by the peer and the misbehaviour until the state takes them to report.
+#[derive(Clone)]
+pub struct PeerGuard {
+    inner: Arc<Mutex<GuardInner>>,
+    clock: Arc<dyn Clock>,
+}
+
+struct GuardInner {
+    limit:   Option<RateLimit>,
+    peers:   HashMap<Address, PeerRecord>,
+    pending: BTreeMap<(Address, Misbehavior), PeerPenalty>,
+}
+
+struct PeerRecord {
+    tokens: f64,
+    refill: Instant,
+    counts: HashMap<Misbehavior, u64>,
+    score:  u64,
+}
+
+impl Debug for PeerGuard {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let inner = self.inner.lock();
+        f.debug_struct("PeerGuard")
+            .field("limit", &inner.limit)
+            .field("peers", &inner.peers.len())
+            .field("pending", &inner.pending.len())
+            .finish()
+    }
+}
+
+impl PeerGuard {
+    /// Create a guard with the rate limit. `None` means no rate limit.
+    pub fn new(clock: Arc<dyn Clock>, limit: Option<RateLimit>) -> Self {
+        PeerGuard {
+            inner: Arc::new(Mutex::new(GuardInner {
+                limit,
+                peers: HashMap::new(),
+                pending: BTreeMap::new(),
+            })),
+            clock,
+        }
+    }
+
+    /// Set the rate limit of each peer. `None` means no rate limit.
+    pub fn set_rate_limit(&self, limit: Option<RateLimit>) {
+        self.inner.lock().limit = limit;
+    }
+
+    /// Check whether a message of the height from the peer in the context should be admitted.
+    /// The messages without a peer or a height are always admitted, since they are not from the
+    /// network. A message of the last height is admitted, since an honest peer which is catching up
+    /// may send it. A message more than one height lower than the current height is dropped, and
+    /// the peer is punished lightly for it.
+    pub fn admit(&self, ctx: &Context, height: Option<u64>, current_height: u64) -> bool {
+        let (peer, height) = match (get_peer(ctx), height) {
+            (Some(peer), Some(height)) => (peer, height),
+            _ => return true,
+        };
+
+        let now = self.clock.now();
+        let mut inner = self.inner.lock();
+        if let Some(limit) = inner.limit {
+            if !inner.record(&peer, now).consume(limit, now) {
+                inner.punish(peer, Misbehavior::ExceedRateLimit, now);
+                return false;
+            }
+        }
+
+        if height + 1 < current_height {
+            inner.punish(peer, Misbehavior::OutdatedMessage, now);
+            return false;
+        }
+        true
+    }
+
+    /// Punish the peer for the misbehaviour.
+    pub fn punish(&self, peer: Address, misbehavior: Misbehavior) {
+        let now = self.clock.now();
+        self.inner.lock().punish(peer, misbehavior, now);
+    }
+
+    /// Take the pending penalties to report.
+    pub fn take_penalties(&self) -> Vec<PeerPenalty> {
+        let pending = mem::replace(&mut self.inner.lock().pending, BTreeMap::new());
+        pending.into_iter().map(|(_, penalty)| penalty).collect()
+    }
+
+    /// Forget a peer, for example, when the peer is disconnected.
+    pub fn forget(&self, peer: &Address) {
+        let mut inner = self.inner.lock();
+        inner.peers.remove(peer);
+        inner.pending = mem::replace(&mut inner.pending, BTreeMap::new())
+            .into_iter()
+            .filter(|((addr, _), _)| addr != peer)
+            .collect();
+    }
+}
+
+impl GuardInner {
+    fn record(&mut self, peer: &Address, now: Instant) -> &mut PeerRecord {
+        let limit = self.limit;
+        self.peers
+            .entry(peer.clone())
+            .or_insert_with(|| PeerRecord::new(limit, now))
+    }
+
+    fn punish(&mut self, peer: Address, misbehavior: Misbehavior, now: Instant) {
+        let record = self.record(&peer, now);
+        let count = record.counts.entry(misbehavior).or_insert(0);
+        *count += 1;
+        record.score += misbehavior.weight();
+
+        let penalty = PeerPenalty {
+            peer: peer.clone(),
+            misbehavior,
+            count: *count,
+            score: record.score,
+        };
+        debug!("Overlord: peer guard punish {}", penalty);
+        self.pending.insert((peer, misbehavior), penalty);
+    }
+}
+
+impl PeerRecord {
+    fn new(limit: Option<RateLimit>, now: Instant) -> Self {
+        PeerRecord {
+            tokens: limit.map_or(0.0, |limit| f64::from(limit.burst)),
+            refill: now,
+            counts: HashMap::new(),
+            score:  0,
+        }
+    }
+
+    /// Refill the token bucket and consume a token if there is one.
+    fn consume(&mut self, limit: RateLimit, now: Instant) -> bool {
+        if now > self.refill {
+            let elapsed = (now - self.refill).as_secs_f64();
+            self.tokens =
+                (self.tokens + elapsed * f64::from(limit.rate)).min(f64::from(limit.burst));
+            self.refill = now;
+        }
+
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use creep::Context;
+
+    use crate::error::ConsensusError;
+    use crate::sim::Simulator;
+    use crate::types::{Misbehavior, PeerPenalty};
+    use crate::RateLimit;
+
+    use super::{get_peer, misbehavior_of, with_peer, PeerGuard};
+
+    #[test]
+    fn test_context_peer() {
+        let peer = Bytes::from(vec![1u8]);
+        assert_eq!(get_peer(&Context::new()), None);
+        assert_eq!(
+            get_peer(&with_peer(&Context::new(), peer.clone())),
+            Some(peer)
+        );
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let simulator = Simulator::new(0);
+        let guard = PeerGuard::new(simulator.clock(), Some(RateLimit { rate: 2, burst: 4 }));
+        let peer = Bytes::from(vec![1u8]);
+        let ctx = with_peer(&Context::new(), peer.clone());
+
+        assert_eq!((0..6).filter(|_| guard.admit(&ctx, Some(1), 1)).count(), 4);
+        // The messages without a peer or a height are not limited.
+        assert!(guard.admit(&Context::new(), Some(1), 1));
+        assert!(guard.admit(&ctx, None, 1));
+
+        simulator.run_for(Duration::from_secs(1));
+        assert_eq!((0..6).filter(|_| guard.admit(&ctx, Some(1), 1)).count(), 2);
+
+        guard.set_rate_limit(None);
+        assert!((0..6).all(|_| guard.admit(&ctx, Some(1), 1)));
+
+        assert_eq!(guard.take_penalties(), vec![PeerPenalty {
+            peer,
+            misbehavior: Misbehavior::ExceedRateLimit,
+            count: 6,
+            score: 6,
+        }]);
+        assert!(guard.take_penalties().is_empty());
+    }
+
+    #[test]
+    fn test_penalty() {
+        let simulator = Simulator::new(0);
+        let guard = PeerGuard::new(simulator.clock(), None);
+        let peer = Bytes::from(vec![1u8]);
+        let ctx = with_peer(&Context::new(), peer.clone());
+
+        // The message of the last height is not outdated.
+        assert!(guard.admit(&ctx, Some(9), 10));
+        assert!(guard.take_penalties().is_empty());
+        assert!(!guard.admit(&ctx, Some(8), 10));
+        assert!(!guard.admit(&ctx, Some(7), 10));
+        assert_eq!(guard.take_penalties(), vec![PeerPenalty {
+            peer:        peer.clone(),
+            misbehavior: Misbehavior::OutdatedMessage,
+            count:       2,
+            score:       2,
+        }]);
+
+        let err = ConsensusError::AggregatedSignatureErr {
+            height: 10,
+            round:  0,
+            reason: "invalid".to_string(),
+        };
+        guard.punish(peer.clone(), misbehavior_of(&err).unwrap());
+        assert_eq!(
+            misbehavior_of(&ConsensusError::InvalidAddress(peer.clone())),
+            Some(Misbehavior::UnknownVoter)
+        );
+        assert_eq!(
+            misbehavior_of(&ConsensusError::WireErr("".to_string())),
+            Some(Misbehavior::InvalidMessage)
+        );
+        // A local crypto failure is not the fault of the peer.
+        let err = ConsensusError::CryptoErr {
+            height: 10,
+            round:  0,
+            source: Box::new(std::fmt::Error),
+        };
+        assert_eq!(misbehavior_of(&err), None);
+        assert_eq!(
+            misbehavior_of(&ConsensusError::StateErr("".to_string())),
+            None
+        );
+
+        assert_eq!(guard.take_penalties(), vec![PeerPenalty {
+            peer:        peer.clone(),
+            misbehavior: Misbehavior::InvalidSignature,
+            count:       1,
+            score:       12,
+        }]);
+
+        guard.punish(peer.clone(), Misbehavior::UnknownVoter);
+        guard.forget(&peer);
+        assert!(guard.take_penalties().is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::{HashSet, VecDeque};
+
+use bytes::Bytes;
+
+use crate::types::Hash;
+
+/// The key of a verified signature, which consists of the message hash, the signature and the
+/// signer. The signer is the address for a signature, or the address bitmap for an aggregated
+/// signature.
+pub type VerifyKey = (Hash, Bytes, Bytes);
+
+/// A bounded cache of the verified signatures. A message which arrives again, such as a QC
+/// embedded in a PoLC or a choke, is recognized by the cache without running the cryptography.
+/// When the cache is full, the earliest verified key is evicted.
+#[derive(Clone, Debug)]
+pub struct VerifyCache {
+    capacity: usize,
+    keys:     HashSet<VerifyKey>,
+    order:    VecDeque<VerifyKey>,
+}
+
+impl VerifyCache {
+    /// Create an empty cache of the capacity.
+    pub fn new(capacity: usize) -> Self {
+        VerifyCache {
+            capacity,
+            keys: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Whether the key has been verified.
+    pub fn contains(&self, key: &VerifyKey) -> bool {
+        self.keys.contains(key)
+    }
+
+    /// Record a verified key.
+    pub fn insert(&mut self, key: VerifyKey) {
+        if self.capacity == 0 || !self.keys.insert(key.clone()) {
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.keys.remove(&evicted);
+            }
+        }
+    }
+
+    /// The number of the cached keys.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+
+    use super::{VerifyCache, VerifyKey};
+
+    fn gen_key(i: u8) -> VerifyKey {
+        (
+            Bytes::from(vec![i]),
+            Bytes::from(vec![i, 1]),
+            Bytes::from(vec![i, 2]),
+        )
+    }
+
+    #[test]
+    fn test_verify_cache() {
+        let mut cache = VerifyCache::new(2);
+        assert!(cache.is_empty());
+        cache.insert(gen_key(0));
+        cache.insert(gen_key(0));
+        assert_eq!(cache.len(), 1);
+
+        // A different signature or signer of the same message is not verified.
+        let (hash, signature, signer) = gen_key(0);
+        assert!(cache.contains(&(hash.clone(), signature.clone(), signer.clone())));
+        assert!(!cache.contains(&(hash.clone(), Bytes::from(vec![9u8]), signer)));
+        assert!(!cache.contains(&(hash, signature, Bytes::from(vec![9u8]))));
+
+        cache.insert(gen_key(1));
+        cache.insert(gen_key(2));
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&gen_key(0)));
+        assert!(cache.contains(&gen_key(1)));
+        assert!(cache.contains(&gen_key(2)));
+    }
+}
@@ -0,0 +1,277 @@
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::ConsensusError;
+use crate::runtime::run_blocking;
+use crate::{ConsensusResult, Spawner, Wal};
+
+const WAL_FILE: &str = "overlord.wal";
+const WAL_TMP_FILE: &str = "overlord.wal.tmp";
+const WAL_MAGIC: &[u8; 4] = b"OVLW";
+/// The header consists of 4 bytes magic, 4 bytes payload length and 4 bytes checksum.
+const HEADER_LEN: usize = 12;
+
+/// A durable wal that saves the wal information into a file. Each save writes the whole wal
+/// information into a temporary file with a checksum header, syncs it to the disk, then renames
+/// it to the wal file atomically. Therefore, the wal file is either the previous one or the new
+/// one. A torn or corrupted wal file is reported as `ConsensusError::LoadWalErr` on loading. The
+/// file I/O runs by `Spawner::spawn_blocking()` of the given spawner, off the async executor.
+#[derive(Clone, Debug)]
+pub struct FileWal {
+    dir:      PathBuf,
+    path:     PathBuf,
+    tmp_path: PathBuf,
+    spawner:  Arc<dyn Spawner>,
+}
+
+impl FileWal {
+    /// Create a file wal in the given directory. The directory is created if it does not exist.
+    pub fn new<P: AsRef<Path>>(dir: P, spawner: Arc<dyn Spawner>) -> ConsensusResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(wal_err)?;
+
+        Ok(FileWal {
+            path: dir.join(WAL_FILE),
+            tmp_path: dir.join(WAL_TMP_FILE),
+            dir,
+            spawner,
+        })
+    }
+
+    /// Get the path of the wal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save_to_file(&self, info: &[u8]) -> ConsensusResult<()> {
+        if info.len() > u32::max_value() as usize {
+            return Err(wal_err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("wal info is too large, length {}", info.len()),
+            )));
+        }
+
+        let mut content = Vec::with_capacity(HEADER_LEN + info.len());
+        content.extend_from_slice(WAL_MAGIC);
+        content.extend_from_slice(&(info.len() as u32).to_be_bytes());
+        content.extend_from_slice(&crc32(info).to_be_bytes());
+        content.extend_from_slice(info);
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.tmp_path)
+            .map_err(wal_err)?;
+        file.write_all(&content).map_err(wal_err)?;
+        file.sync_all().map_err(wal_err)?;
+        fs::rename(&self.tmp_path, &self.path).map_err(wal_err)?;
+
+        // Sync the directory to make the rename durable.
+        #[cfg(unix)]
+        File::open(&self.dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(wal_err)?;
+        Ok(())
+    }
+
+    fn load_from_file(&self) -> ConsensusResult<Option<Bytes>> {
+        let content = match fs::read(&self.path) {
+            Ok(content) => content,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(wal_err(err)),
+        };
+
+        if content.len() < HEADER_LEN {
+            return Err(ConsensusError::LoadWalErr(format!(
+                "wal file is torn, header expects {} bytes, found {}",
+                HEADER_LEN,
+                content.len()
+            )));
+        }
+
+        if &content[0..4] != WAL_MAGIC {
+            return Err(ConsensusError::LoadWalErr(
+                "wal file is corrupted, invalid magic".to_string(),
+            ));
+        }
+
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&content[4..8]);
+        let len = u32::from_be_bytes(len) as usize;
+        let mut checksum = [0u8; 4];
+        checksum.copy_from_slice(&content[8..12]);
+        let checksum = u32::from_be_bytes(checksum);
+
+        let info = &content[HEADER_LEN..];
+        if info.len() != len {
+            return Err(ConsensusError::LoadWalErr(format!(
+                "wal file is torn, payload expects {} bytes, found {}",
+                len,
+                info.len()
+            )));
+        }
+
+        if crc32(info) != checksum {
+            return Err(ConsensusError::LoadWalErr(
+                "wal file is corrupted, checksum mismatch".to_string(),
+            ));
+        }
+
+        Ok(Some(Bytes::from(info.to_vec())))
+    }
+}
+
+#[async_trait]
+impl Wal for FileWal {
+    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let wal = self.clone();
+        run_blocking(self.spawner.as_ref(), move || {
+            wal.save_to_file(info.as_ref())
+        })
+        .await
+        .unwrap_or_else(|| Err(canceled_err()))
+        .map_err(|err| Box::new(err) as Box<dyn Error + Send + Sync>)
+    }
+
+    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send + Sync>> {
+        let wal = self.clone();
+        run_blocking(self.spawner.as_ref(), move || wal.load_from_file())
+            .await
+            .unwrap_or_else(|| Err(canceled_err()))
+            .map_err(|err| Box::new(err) as Box<dyn Error + Send + Sync>)
+    }
+}
+
+fn wal_err(err: io::Error) -> ConsensusError {
+    ConsensusError::WalErr(Box::new(err))
+}
+
+fn canceled_err() -> ConsensusError {
+    wal_err(io::Error::new(
+        io::ErrorKind::Other,
+        "the blocking wal task is canceled",
+    ))
+}
+
+/// CRC-32 (IEEE) checksum.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data.iter() {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (!(crc & 1)).wrapping_add(1);
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::thread;
+
+    use bytes::Bytes;
+    use futures::executor::block_on;
+    use futures::future::BoxFuture;
+
+    use crate::error::ConsensusError;
+    use crate::{Spawner, Wal};
+
+    use super::{crc32, FileWal, HEADER_LEN};
+
+    #[derive(Debug)]
+    struct ThreadSpawner;
+
+    impl Spawner for ThreadSpawner {
+        fn spawn(&self, future: BoxFuture<'static, ()>) {
+            thread::spawn(move || block_on(future));
+        }
+    }
+
+    fn gen_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("overlord_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn is_load_wal_err(err: Box<dyn std::error::Error + Send + Sync>) -> bool {
+        if let Ok(err) = err.downcast::<ConsensusError>() {
+            if let ConsensusError::LoadWalErr(_) = *err {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = gen_dir("save_and_load");
+        let wal = FileWal::new(&dir, Arc::new(ThreadSpawner)).unwrap();
+
+        block_on(async {
+            assert_eq!(wal.load().await.unwrap(), None);
+
+            wal.save(Bytes::from(vec![1u8, 2, 3])).await.unwrap();
+            assert_eq!(
+                wal.load().await.unwrap(),
+                Some(Bytes::from(vec![1u8, 2, 3]))
+            );
+
+            wal.save(Bytes::from(vec![4u8; 100])).await.unwrap();
+            assert_eq!(wal.load().await.unwrap(), Some(Bytes::from(vec![4u8; 100])));
+
+            // Reopen the wal from the same directory.
+            let wal = FileWal::new(&dir, Arc::new(ThreadSpawner)).unwrap();
+            assert_eq!(wal.load().await.unwrap(), Some(Bytes::from(vec![4u8; 100])));
+        });
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_torn_and_corrupted() {
+        let dir = gen_dir("torn_and_corrupted");
+        let wal = FileWal::new(&dir, Arc::new(ThreadSpawner)).unwrap();
+
+        block_on(async {
+            wal.save(Bytes::from(vec![7u8; 32])).await.unwrap();
+            let content = fs::read(wal.path()).unwrap();
+
+            // Torn header.
+            fs::write(wal.path(), &content[..HEADER_LEN - 1]).unwrap();
+            assert!(is_load_wal_err(wal.load().await.unwrap_err()));
+
+            // Torn payload.
+            fs::write(wal.path(), &content[..content.len() - 1]).unwrap();
+            assert!(is_load_wal_err(wal.load().await.unwrap_err()));
+
+            // Corrupted payload.
+            let mut corrupted = content.clone();
+            corrupted[HEADER_LEN] ^= 0xff;
+            fs::write(wal.path(), &corrupted).unwrap();
+            assert!(is_load_wal_err(wal.load().await.unwrap_err()));
+
+            // Invalid magic.
+            let mut corrupted = content.clone();
+            corrupted[0] = 0;
+            fs::write(wal.path(), &corrupted).unwrap();
+            assert!(is_load_wal_err(wal.load().await.unwrap_err()));
+        });
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,3 +1,7 @@
+mod file_wal;
+mod version;
 mod wal_type;
 
-pub use self::wal_type::{SMRBase, WalInfo, WalLock};
+pub use self::file_wal::FileWal;
+pub use self::version::WAL_VERSION;
+pub use self::wal_type::{SMRBase, WalInfo, WalLock, Watermark};
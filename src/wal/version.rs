@@ -0,0 +1,266 @@
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use rlp::{DecoderError, Prototype, Rlp, RlpStream};
+
+use crate::error::ConsensusError;
+use crate::smr::smr_types::Step;
+use crate::types::{Address, AggregatedChoke, AggregatedSignature, Node, Signature, UpdateFrom};
+use crate::utils::auth_manage::AuthorityManage;
+use crate::wal::{WalInfo, WalLock};
+use crate::{Codec, ConsensusResult};
+
+/// The current version of the wal format.
+pub const WAL_VERSION: u8 = 2;
+
+/// The wal format is an envelope of `[version, rlp(WalInfo)]`. The legacy format before versioning
+/// is a bare `rlp(WalInfo)`, which is regarded as version 0.
+///
+/// Version 0 differs from version 1 in the choke QC of `UpdateFrom`, which is encoded as
+/// `[height, round, signature, voters]` rather than `[height, round, aggregated signature]`.
+///
+/// Version 1 differs from version 2 in the signing watermark, which is appended to the wal
+/// information in version 2. The watermark of a version 1 wal is none.
+impl<T: Codec> WalInfo<T> {
+    /// Encode the wal information into the current version of the wal format.
+    pub fn to_wal_bytes(&self) -> Bytes {
+        let mut s = RlpStream::new_list(2);
+        s.append(&WAL_VERSION).append(&rlp::encode(self));
+        Bytes::from(s.out())
+    }
+
+    /// Decode the wal information from any supported version of the wal format. The
+    /// `authority_list` is used to rebuild the address bitmap of a version 0 choke QC, which only
+    /// saves the voters.
+    pub fn from_wal_bytes(info: &[u8], authority_list: &[Node]) -> ConsensusResult<Self> {
+        let r = Rlp::new(info);
+        match r.prototype().map_err(decode_err)? {
+            Prototype::List(2) => {
+                let version: u8 = r.val_at(0).map_err(decode_err)?;
+                let content: Vec<u8> = r.val_at(1).map_err(decode_err)?;
+                match version {
+                    1 | WAL_VERSION => rlp::decode(&content).map_err(decode_err),
+                    _ => Err(ConsensusError::LoadWalErr(format!(
+                        "unsupported wal version {}, the latest version is {}",
+                        version, WAL_VERSION
+                    ))),
+                }
+            }
+            Prototype::List(5) => {
+                let mut auth_list = authority_list.to_vec();
+                let mut authority = AuthorityManage::new();
+                authority.update(&mut auth_list);
+                decode_v0(&r, &authority).map_err(decode_err)
+            }
+            _ => Err(ConsensusError::LoadWalErr("unknown wal format".to_string())),
+        }
+    }
+}
+
+fn decode_v0<T: Codec>(r: &Rlp, authority: &AuthorityManage) -> Result<WalInfo<T>, DecoderError> {
+    let height: u64 = r.val_at(0)?;
+    let round: u64 = r.val_at(1)?;
+    let tmp: u8 = r.val_at(2)?;
+    let step = Step::try_from(tmp)?;
+    let lock: Option<WalLock<T>> = r.val_at(3)?;
+    let from = decode_v0_update_from(&r.at(4)?, authority)?;
+    Ok(WalInfo {
+        height,
+        round,
+        step,
+        lock,
+        from,
+        watermark: None,
+    })
+}
+
+fn decode_v0_update_from(r: &Rlp, authority: &AuthorityManage) -> Result<UpdateFrom, DecoderError> {
+    match r.prototype()? {
+        Prototype::List(2) => {
+            let tmp: u8 = r.val_at(0)?;
+            match tmp {
+                0u8 => Ok(UpdateFrom::PrevoteQC(r.val_at(1)?)),
+                1u8 => Ok(UpdateFrom::PrecommitQC(r.val_at(1)?)),
+                2u8 => {
+                    let choke = r.at(1)?;
+                    if choke.item_count()? != 4 {
+                        return Err(DecoderError::RlpIncorrectListLen);
+                    }
+
+                    let height: u64 = choke.val_at(0)?;
+                    let round: u64 = choke.val_at(1)?;
+                    let tmp: Vec<u8> = choke.val_at(2)?;
+                    let signature = Signature::from(tmp);
+                    let tmp: Vec<Vec<u8>> = choke.list_at(3)?;
+                    let voters = tmp.into_iter().map(Address::from).collect::<Vec<_>>();
+                    Ok(UpdateFrom::ChokeQC(AggregatedChoke {
+                        height,
+                        round,
+                        signature: AggregatedSignature {
+                            signature,
+                            address_bitmap: authority.get_bitmap(&voters),
+                        },
+                    }))
+                }
+                _ => Err(DecoderError::Custom("Invalid update from type")),
+            }
+        }
+        _ => Err(DecoderError::RlpInconsistentLengthAndData),
+    }
+}
+
+fn decode_err(err: DecoderError) -> ConsensusError {
+    ConsensusError::LoadWalErr(format!("decode wal error {:?}", err))
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+
+    use bytes::Bytes;
+
+    use crate::error::ConsensusError;
+    use crate::smr::smr_types::Step;
+    use crate::types::{
+        AggregatedChoke, AggregatedSignature, AggregatedVote, Node, UpdateFrom, VoteType,
+    };
+    use crate::wal::{WalInfo, WalLock, Watermark};
+    use crate::Codec;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Pill {
+        inner: Vec<u8>,
+    }
+
+    impl Codec for Pill {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+            Ok(Bytes::from(self.inner.clone()))
+        }
+
+        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
+            Ok(Pill {
+                inner: data.as_ref().to_vec(),
+            })
+        }
+    }
+
+    fn golden(content: &str) -> Vec<u8> {
+        hex::decode(content.trim()).unwrap()
+    }
+
+    fn gen_authority_list() -> Vec<Node> {
+        vec![
+            Node::new(Bytes::from(vec![0x03u8; 4])),
+            Node::new(Bytes::from(vec![0x01u8; 4])),
+            Node::new(Bytes::from(vec![0x02u8; 4])),
+        ]
+    }
+
+    fn gen_lock_wal_info() -> WalInfo<Pill> {
+        let qc = AggregatedVote {
+            signature:  AggregatedSignature {
+                signature:      Bytes::from(vec![0xaau8; 4]),
+                address_bitmap: Bytes::from(vec![0xc0u8]),
+            },
+            vote_type:  VoteType::Prevote,
+            height:     10,
+            round:      1,
+            block_hash: Bytes::from(vec![0xbbu8; 4]),
+            leader:     Bytes::from(vec![0x01u8; 4]),
+        };
+
+        WalInfo {
+            height:    10,
+            round:     2,
+            step:      Step::Precommit,
+            lock:      Some(WalLock {
+                lock_round: 1,
+                lock_votes: qc.clone(),
+                content:    Pill {
+                    inner: vec![0xcc, 0xdd],
+                },
+            }),
+            from:      UpdateFrom::PrevoteQC(qc),
+            watermark: None,
+        }
+    }
+
+    fn gen_choke_wal_info() -> WalInfo<Pill> {
+        WalInfo {
+            height:    11,
+            round:     3,
+            step:      Step::Brake,
+            lock:      None,
+            from:      UpdateFrom::ChokeQC(AggregatedChoke {
+                height:    11,
+                round:     2,
+                signature: AggregatedSignature {
+                    signature:      Bytes::from(vec![0xeeu8; 4]),
+                    address_bitmap: Bytes::from(vec![0xc0u8]),
+                },
+            }),
+            watermark: None,
+        }
+    }
+
+    fn gen_watermark() -> Watermark {
+        Watermark {
+            height: 10,
+            round:  2,
+            step:   Step::Precommit,
+            hash:   Bytes::from(vec![0xffu8; 4]),
+        }
+    }
+
+    #[test]
+    fn test_golden_v0() {
+        let info = golden(include_str!("golden/wal_v0_lock.hex"));
+        let res = WalInfo::<Pill>::from_wal_bytes(&info, &gen_authority_list()).unwrap();
+        assert_eq!(res, gen_lock_wal_info());
+
+        let info = golden(include_str!("golden/wal_v0_choke.hex"));
+        let res = WalInfo::<Pill>::from_wal_bytes(&info, &gen_authority_list()).unwrap();
+        assert_eq!(res, gen_choke_wal_info());
+    }
+
+    #[test]
+    fn test_golden_v1() {
+        let info = golden(include_str!("golden/wal_v1_lock.hex"));
+        let res = WalInfo::<Pill>::from_wal_bytes(&info, &[]).unwrap();
+        assert_eq!(res, gen_lock_wal_info());
+
+        let info = golden(include_str!("golden/wal_v1_choke.hex"));
+        let res = WalInfo::<Pill>::from_wal_bytes(&info, &[]).unwrap();
+        assert_eq!(res, gen_choke_wal_info());
+    }
+
+    #[test]
+    fn test_golden_v2() {
+        let mut lock_wal_info = gen_lock_wal_info();
+        lock_wal_info.watermark = Some(gen_watermark());
+        let info = golden(include_str!("golden/wal_v2_lock.hex"));
+        let res = WalInfo::<Pill>::from_wal_bytes(&info, &[]).unwrap();
+        assert_eq!(res, lock_wal_info);
+        assert_eq!(lock_wal_info.to_wal_bytes().as_ref(), info.as_slice());
+
+        let info = golden(include_str!("golden/wal_v2_choke.hex"));
+        let res = WalInfo::<Pill>::from_wal_bytes(&info, &[]).unwrap();
+        assert_eq!(res, gen_choke_wal_info());
+        assert_eq!(
+            gen_choke_wal_info().to_wal_bytes().as_ref(),
+            info.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut s = rlp::RlpStream::new_list(2);
+        s.append(&(super::WAL_VERSION + 1))
+            .append(&rlp::encode(&gen_lock_wal_info()));
+
+        match WalInfo::<Pill>::from_wal_bytes(&s.out(), &[]) {
+            Err(ConsensusError::LoadWalErr(_)) => (),
+            _ => panic!("an unsupported wal version should be rejected"),
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
 
+use crate::error::ConsensusError;
 use crate::smr::smr_types::{Lock, Step};
-use crate::types::{AggregatedVote, UpdateFrom};
-use crate::Codec;
+use crate::types::{AggregatedVote, Hash, UpdateFrom};
+use crate::{Codec, ConsensusResult};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Display, Eq, PartialEq)]
 #[rustfmt::skip]
@@ -14,15 +15,18 @@ use crate::Codec;
 /// Structure of Wal Info
 pub struct WalInfo<T: Codec> {
     /// height
-    pub height: u64,
+    pub height:    u64,
     /// round
-    pub round:  u64,
+    pub round:     u64,
     /// step
-    pub step:   Step,
+    pub step:      Step,
     /// lock
-    pub lock:   Option<WalLock<T>>,
+    pub lock:      Option<WalLock<T>>,
     /// from
-    pub from:   UpdateFrom,
+    pub from:      UpdateFrom,
+    /// watermark
+    #[serde(default)]
+    pub watermark: Option<Watermark>,
 }
 
 impl<T: Codec> WalInfo<T> {
@@ -39,13 +43,18 @@ impl<T: Codec> WalInfo<T> {
 
 #[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
 #[display(fmt = "wal lock round {}, qc {:?}", lock_round, lock_votes)]
+/// Structure of Wal Lock
 pub struct WalLock<T: Codec> {
+    /// lock round
     pub lock_round: u64,
+    /// prevote QC of the lock
     pub lock_votes: AggregatedVote,
-    pub content:    T,
+    /// locked block
+    pub content: T,
 }
 
 impl<T: Codec> WalLock<T> {
+    /// transfer WalLock to Lock
     pub fn to_lock(&self) -> Lock {
         Lock {
             round: self.lock_round,
@@ -54,6 +63,52 @@ impl<T: Codec> WalLock<T> {
     }
 }
 
+/// The watermark of the latest signature of self, which is persisted before signing. The hash is
+/// the hash of the signed message, so that two messages with the same hash are identical.
+#[derive(Serialize, Deserialize, Clone, Debug, Display, PartialEq, Eq)]
+#[display(fmt = "watermark height {}, round {}, step {:?}", height, round, step)]
+pub struct Watermark {
+    /// height
+    pub height: u64,
+    /// round
+    pub round: u64,
+    /// step
+    pub step: Step,
+    /// hash
+    #[serde(with = "crate::serde_hex")]
+    pub hash: Hash,
+}
+
+impl Watermark {
+    /// Check whether a message can be signed under the watermark. Return `Ok(true)` if the message
+    /// is identical to the latest signed one, and `Ok(false)` if the message is above the
+    /// watermark. A message below the watermark, or a different message at the same height, round
+    /// and step, is refused since it may cause a double sign.
+    pub fn check(&self, msg: &Watermark) -> ConsensusResult<bool> {
+        let position = (self.height, self.round, &self.step);
+        let msg_position = (msg.height, msg.round, &msg.step);
+
+        if msg_position > position {
+            Ok(false)
+        } else if msg_position == position && msg.hash == self.hash {
+            Ok(true)
+        } else if msg_position == position {
+            Err(ConsensusError::DoubleSignErr(format!(
+                "conflict with the signed message of hash {:?} in height {}, round {}, step {:?}",
+                hex::encode(self.hash.clone()),
+                self.height,
+                self.round,
+                self.step
+            )))
+        } else {
+            Err(ConsensusError::DoubleSignErr(format!(
+                "height {}, round {}, step {:?} is below the {}",
+                msg.height, msg.round, msg.step, self
+            )))
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SMRBase {
     pub height: u64,
@@ -78,11 +133,11 @@ mod test {
     }
 
     impl Codec for Pill {
-        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
             Ok(Bytes::from(self.inner.clone()))
         }
 
-        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
             Ok(Pill {
                 inner: data.as_ref().to_vec(),
             })
@@ -123,11 +178,12 @@ mod test {
         println!("{}", wal_lock);
 
         let wal_info = WalInfo {
-            height: 0,
-            round:  0,
-            step:   Step::Propose,
-            lock:   Some(wal_lock),
-            from:   UpdateFrom::PrecommitQC(mock_qc()),
+            height:    0,
+            round:     0,
+            step:      Step::Propose,
+            lock:      Some(wal_lock),
+            from:      UpdateFrom::PrecommitQC(mock_qc()),
+            watermark: None,
         };
 
         assert_eq!(
@@ -135,4 +191,40 @@ mod test {
             "wal info height 0, round 0, step Propose"
         );
     }
+
+    #[test]
+    fn test_watermark_check() {
+        let watermark = |height: u64, round: u64, step: Step, hash: u8| Watermark {
+            height,
+            round,
+            step,
+            hash: Bytes::from(vec![hash]),
+        };
+        let latest = watermark(10, 2, Step::Prevote, 1);
+
+        // Retransmit the identical message.
+        assert_eq!(latest.check(&watermark(10, 2, Step::Prevote, 1)), Ok(true));
+        // Sign a message above the watermark.
+        assert_eq!(
+            latest.check(&watermark(10, 2, Step::Precommit, 2)),
+            Ok(false)
+        );
+        assert_eq!(latest.check(&watermark(10, 3, Step::Propose, 2)), Ok(false));
+        assert_eq!(latest.check(&watermark(11, 0, Step::Propose, 2)), Ok(false));
+
+        // Refuse a conflicting message or a message below the watermark.
+        for msg in vec![
+            watermark(10, 2, Step::Prevote, 2),
+            watermark(10, 2, Step::Propose, 1),
+            watermark(10, 1, Step::Brake, 1),
+            watermark(9, 5, Step::Precommit, 1),
+        ]
+        .iter()
+        {
+            match latest.check(msg) {
+                Err(ConsensusError::DoubleSignErr(_)) => (),
+                res => panic!("{} should be refused, but get {:?}", msg, res),
+            }
+        }
+    }
 }
@@ -0,0 +1,326 @@
+use std::convert::TryFrom;
+
+use bytes::Bytes;
+use rlp::{Decodable, DecoderError, Prototype, Rlp, RlpStream};
+
+use crate::error::ConsensusError;
+use crate::types::OverlordMsg;
+use crate::{Codec, ConsensusResult, WireLimit};
+
+/// The current version of the wire protocol.
+pub const WIRE_VERSION: u8 = 1;
+
+/// The kind of a message on the wire.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MsgKind {
+    SignedProposal,
+    SignedVote,
+    AggregatedVote,
+    SignedChoke,
+}
+
+impl Into<u8> for MsgKind {
+    fn into(self) -> u8 {
+        match self {
+            MsgKind::SignedProposal => 0,
+            MsgKind::SignedVote => 1,
+            MsgKind::AggregatedVote => 2,
+            MsgKind::SignedChoke => 3,
+        }
+    }
+}
+
+impl TryFrom<u8> for MsgKind {
+    type Error = DecoderError;
+
+    fn try_from(s: u8) -> Result<Self, Self::Error> {
+        match s {
+            0 => Ok(MsgKind::SignedProposal),
+            1 => Ok(MsgKind::SignedVote),
+            2 => Ok(MsgKind::AggregatedVote),
+            3 => Ok(MsgKind::SignedChoke),
+            _ => Err(DecoderError::Custom("Invalid message kind")),
+        }
+    }
+}
+
+/// The wire format is an envelope of `[version, kind, rlp(message)]`. Only the messages between
+/// the nodes, which are the signed proposals, signed votes, QCs and signed chokes, can be put on
+/// the wire. The version and the kind are read before the message, so that a message of an
+/// unsupported version or an oversized message is rejected before decoding the block content.
+impl<T: Codec> OverlordMsg<T> {
+    /// Encode the message into the current version of the wire format.
+    pub fn to_wire_bytes(&self) -> ConsensusResult<Bytes> {
+        let (kind, msg) = match self {
+            OverlordMsg::SignedProposal(sp) => {
+                // Encode the content first, so that a failure of the codec is not a panic.
+                let content = sp.proposal.content.encode().map_err(|e| {
+                    ConsensusError::WireErr(format!("encode the content of {} error {}", self, e))
+                })?;
+                (MsgKind::SignedProposal, sp.rlp_bytes_with_content(&content))
+            }
+            OverlordMsg::SignedVote(sv) => (MsgKind::SignedVote, rlp::encode(sv)),
+            OverlordMsg::AggregatedVote(av) => (MsgKind::AggregatedVote, rlp::encode(av)),
+            OverlordMsg::SignedChoke(sc) => (MsgKind::SignedChoke, rlp::encode(sc)),
+            _ => {
+                return Err(ConsensusError::WireErr(format!(
+                    "{} can not be put on the wire",
+                    self
+                )))
+            }
+        };
+
+        let mut s = RlpStream::new_list(3);
+        s.append(&WIRE_VERSION)
+            .append::<u8>(&kind.into())
+            .append(&msg);
+        Ok(Bytes::from(s.out()))
+    }
+
+    /// Decode the message from the wire format. A signed proposal larger than
+    /// `limit.max_proposal_size`, or another message larger than `limit.max_msg_size`, is
+    /// rejected.
+    pub fn from_wire_bytes(data: &[u8], limit: WireLimit) -> ConsensusResult<Self> {
+        let r = Rlp::new(data);
+        match r.prototype().map_err(decode_err)? {
+            Prototype::List(3) => (),
+            _ => return Err(ConsensusError::WireErr("unknown wire format".to_string())),
+        }
+
+        let version: u8 = r.val_at(0).map_err(decode_err)?;
+        if version != WIRE_VERSION {
+            return Err(ConsensusError::WireErr(format!(
+                "unsupported wire version {}, the latest version is {}",
+                version, WIRE_VERSION
+            )));
+        }
+
+        let tmp: u8 = r.val_at(1).map_err(decode_err)?;
+        let kind = MsgKind::try_from(tmp).map_err(decode_err)?;
+        let max_size = if kind == MsgKind::SignedProposal {
+            limit.max_proposal_size
+        } else {
+            limit.max_msg_size
+        };
+        if data.len() > max_size {
+            return Err(ConsensusError::WireErr(format!(
+                "{:?} of {} bytes exceeds the limit of {} bytes",
+                kind,
+                data.len(),
+                max_size
+            )));
+        }
+
+        let msg = r.at(2).and_then(|msg| msg.data()).map_err(decode_err)?;
+        let res = match kind {
+            MsgKind::SignedProposal => OverlordMsg::SignedProposal(decode(msg)?),
+            MsgKind::SignedVote => OverlordMsg::SignedVote(decode(msg)?),
+            MsgKind::AggregatedVote => OverlordMsg::AggregatedVote(decode(msg)?),
+            MsgKind::SignedChoke => OverlordMsg::SignedChoke(decode(msg)?),
+        };
+        Ok(res)
+    }
+}
+
+fn decode<D: Decodable>(msg: &[u8]) -> ConsensusResult<D> {
+    rlp::decode(msg).map_err(decode_err)
+}
+
+fn decode_err(err: DecoderError) -> ConsensusError {
+    ConsensusError::WireErr(format!("decode wire message error {:?}", err))
+}
+
+#[cfg(test)]
+mod test {
+    use std::error::Error;
+
+    use bytes::Bytes;
+
+    use crate::error::ConsensusError;
+    use crate::types::{
+        AggregatedSignature, AggregatedVote, Choke, OverlordMsg, Proposal, SignedChoke,
+        SignedProposal, SignedVote, UpdateFrom, Vote, VoteType,
+    };
+    use crate::{Codec, WireLimit};
+
+    use super::WIRE_VERSION;
+
+    /// A content which panics when decoding, to make sure that an oversized proposal is rejected
+    /// before decoding the content.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Pill {
+        inner: Vec<u8>,
+    }
+
+    impl Codec for Pill {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+            Ok(Bytes::from(self.inner.clone()))
+        }
+
+        fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
+            assert!(data.len() < 1024, "decode an oversized content");
+            Ok(Pill {
+                inner: data.as_ref().to_vec(),
+            })
+        }
+    }
+
+    fn gen_vote() -> SignedVote {
+        SignedVote {
+            signature: Bytes::from(vec![0xaau8; 4]),
+            vote:      Vote {
+                height:     10,
+                round:      2,
+                vote_type:  VoteType::Prevote,
+                block_hash: Bytes::from(vec![0xbbu8; 4]),
+            },
+            voter:     Bytes::from(vec![0x01u8; 4]),
+        }
+    }
+
+    fn gen_qc() -> AggregatedVote {
+        AggregatedVote {
+            signature:  AggregatedSignature {
+                signature:      Bytes::from(vec![0xaau8; 4]),
+                address_bitmap: Bytes::from(vec![0xc0u8]),
+            },
+            vote_type:  VoteType::Precommit,
+            height:     10,
+            round:      2,
+            block_hash: Bytes::from(vec![0xbbu8; 4]),
+            leader:     Bytes::from(vec![0x01u8; 4]),
+        }
+    }
+
+    fn gen_proposal(size: usize) -> OverlordMsg<Pill> {
+        OverlordMsg::SignedProposal(SignedProposal {
+            signature: Bytes::from(vec![0xaau8; 4]),
+            proposal:  Proposal {
+                height:     10,
+                round:      2,
+                content:    Pill {
+                    inner: vec![0xccu8; size],
+                },
+                block_hash: Bytes::from(vec![0xbbu8; 4]),
+                lock:       None,
+                proposer:   Bytes::from(vec![0x01u8; 4]),
+            },
+        })
+    }
+
+    #[test]
+    fn test_wire_codec() {
+        let msgs = vec![
+            gen_proposal(16),
+            OverlordMsg::SignedVote(gen_vote()),
+            OverlordMsg::AggregatedVote(gen_qc()),
+            OverlordMsg::SignedChoke(SignedChoke {
+                signature: Bytes::from(vec![0xaau8; 4]),
+                choke:     Choke {
+                    height: 10,
+                    round:  2,
+                    from:   UpdateFrom::PrecommitQC(gen_qc()),
+                },
+                address:   Bytes::from(vec![0x01u8; 4]),
+            }),
+        ];
+
+        for msg in msgs.into_iter() {
+            let data = msg.to_wire_bytes().unwrap();
+            let res = OverlordMsg::from_wire_bytes(&data, WireLimit::default()).unwrap();
+            assert_eq!(res, msg);
+        }
+
+        match OverlordMsg::<Pill>::Stop.to_wire_bytes() {
+            Err(ConsensusError::WireErr(_)) => (),
+            _ => panic!("a stop message should not be put on the wire"),
+        }
+    }
+
+    /// A content which fails to encode.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Broken;
+
+    impl Codec for Broken {
+        fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+            Err(Box::new(std::fmt::Error))
+        }
+
+        fn decode(_data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
+            Ok(Broken)
+        }
+    }
+
+    #[test]
+    fn test_wire_encode_error() {
+        let msg = OverlordMsg::SignedProposal(SignedProposal {
+            signature: Bytes::from(vec![0xaau8; 4]),
+            proposal:  Proposal {
+                height:     10,
+                round:      2,
+                content:    Broken,
+                block_hash: Bytes::from(vec![0xbbu8; 4]),
+                lock:       None,
+                proposer:   Bytes::from(vec![0x01u8; 4]),
+            },
+        });
+        match msg.to_wire_bytes() {
+            Err(ConsensusError::WireErr(_)) => (),
+            res => panic!(
+                "a content failing to encode should be an error, but get {:?}",
+                res
+            ),
+        }
+    }
+
+    #[test]
+    fn test_golden_wire() {
+        let data = hex::decode("d7010194d384aaaaaaaac80a020184bbbbbbbb8401010101").unwrap();
+        let msg = OverlordMsg::<Pill>::SignedVote(gen_vote());
+        assert_eq!(msg.to_wire_bytes().unwrap().as_ref(), data.as_slice());
+        assert_eq!(
+            OverlordMsg::from_wire_bytes(&data, WireLimit::default()).unwrap(),
+            msg
+        );
+    }
+
+    #[test]
+    fn test_wire_reject() {
+        let limit = WireLimit {
+            max_proposal_size: 1024,
+            max_msg_size:      16,
+        };
+        assert!(
+            OverlordMsg::from_wire_bytes(&gen_proposal(512).to_wire_bytes().unwrap(), limit)
+                .is_ok()
+        );
+
+        // Oversized messages.
+        let mut invalid = vec![
+            gen_proposal(2048).to_wire_bytes().unwrap().to_vec(),
+            OverlordMsg::<Pill>::AggregatedVote(gen_qc())
+                .to_wire_bytes()
+                .unwrap()
+                .to_vec(),
+        ];
+
+        // Unsupported version and unknown kind.
+        let vote = rlp::encode(&gen_vote());
+        for (version, kind) in [(WIRE_VERSION + 1, 1u8), (WIRE_VERSION, 9u8)].iter() {
+            let mut s = rlp::RlpStream::new_list(3);
+            s.append(version).append(kind).append(&vote);
+            invalid.push(s.out());
+        }
+
+        // Malformed envelope.
+        invalid.push(vec![0xc0]);
+        invalid.push(rlp::encode(&gen_vote()));
+
+        for data in invalid.iter() {
+            match OverlordMsg::<Pill>::from_wire_bytes(data, limit) {
+                Err(ConsensusError::WireErr(_)) => (),
+                res => panic!("{:?} should be rejected, but get {:?}", data, res),
+            }
+        }
+    }
+}
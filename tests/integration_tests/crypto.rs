@@ -18,7 +18,7 @@ impl Crypto for MockCrypto {
         hash(&speech)
     }
 
-    fn sign(&self, _hash: Bytes) -> Result<Bytes, Box<dyn Error + Send>> {
+    fn sign(&self, _hash: Bytes) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
         Ok(self.name.clone())
     }
 
@@ -26,7 +26,7 @@ impl Crypto for MockCrypto {
         &self,
         _signatures: Vec<Bytes>,
         _speaker: Vec<Bytes>,
-    ) -> Result<Bytes, Box<dyn Error + Send>> {
+    ) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
         Ok(Bytes::new())
     }
 
@@ -35,7 +35,7 @@ impl Crypto for MockCrypto {
         _signature: Bytes,
         _hash: Bytes,
         _voter: Bytes,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -44,7 +44,7 @@ impl Crypto for MockCrypto {
         _aggregated_signature: Bytes,
         _hash: Bytes,
         _voters: Vec<Bytes>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }
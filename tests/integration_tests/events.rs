@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use creep::Context;
+use futures::future::{self, Either};
+use futures::StreamExt;
+use futures_timer::Delay;
+
+use overlord::types::{ConsensusEvent, OverlordMsg};
+
+use super::run::run_single_node;
+use super::wal::Record;
+
+#[tokio::test(threaded_scheduler)]
+async fn test_subscribe_events() {
+    let records = Record::new(1, 10);
+    let (node, sender) = run_single_node(&records);
+    let mut events = node.handler.subscribe();
+
+    // Collect events until a whole height is observed.
+    let collect = async {
+        let mut observed = Vec::new();
+        let mut start = None;
+        while let Some(event) = events.next().await {
+            match &event {
+                ConsensusEvent::NewHeight { height } if start.is_none() => start = Some(*height),
+                ConsensusEvent::Commit { height, .. } if Some(*height) == start => {
+                    observed.push(event);
+                    break;
+                }
+                _ => (),
+            }
+            if start.is_some() {
+                observed.push(event);
+            }
+        }
+        (start, observed)
+    };
+    let timeout = Delay::new(Duration::from_secs(30));
+    let (start, observed) = match future::select(Box::pin(collect), timeout).await {
+        Either::Left((res, _)) => res,
+        Either::Right(_) => panic!("no whole height is observed in 30 seconds"),
+    };
+
+    let height = start.expect("the event stream ends before a new height");
+    assert_eq!(observed[0], ConsensusEvent::NewHeight { height });
+    assert_eq!(observed[1], ConsensusEvent::NewRound { height, round: 0 });
+    match &observed[2] {
+        ConsensusEvent::ProposalReceived {
+            height: h,
+            proposer,
+            ..
+        } => {
+            assert_eq!(*h, height);
+            assert_eq!(*proposer, node.adapter.address);
+        }
+        event => panic!("unexpected event {}", event),
+    }
+    assert!(observed.iter().any(|event| match event {
+        ConsensusEvent::PrevoteQC(qc) => qc.height == height,
+        _ => false,
+    }));
+    assert!(observed.iter().any(|event| match event {
+        ConsensusEvent::PrecommitQC(qc) => qc.height == height,
+        _ => false,
+    }));
+    match observed.last().unwrap() {
+        ConsensusEvent::Commit { proof, .. } => assert_eq!(proof.height, height),
+        event => panic!("unexpected event {}", event),
+    }
+
+    node.handler
+        .send_msg(Context::new(), OverlordMsg::Stop)
+        .unwrap();
+    sender.send(OverlordMsg::Stop).unwrap();
+}
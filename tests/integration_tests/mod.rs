@@ -1,6 +1,10 @@
 mod crypto;
+mod events;
 mod primitive;
 mod run;
+mod signer;
+mod sim;
+mod status;
 mod utils;
 mod wal;
 
@@ -9,8 +9,10 @@ use creep::Context;
 use crossbeam_channel::{Receiver, Sender};
 use serde::{Deserialize, Serialize};
 
+use overlord::election::RoundRobin;
 use overlord::error::ConsensusError;
-use overlord::types::{Commit, Hash, Node, OverlordMsg, Status};
+use overlord::runtime::TokioSpawner;
+use overlord::types::{Commit, Evidence, Hash, Node, OverlordMsg, PeerPenalty, Status};
 use overlord::{Codec, Consensus, DurationConfig, Overlord, OverlordHandler};
 
 use super::crypto::MockCrypto;
@@ -33,11 +35,11 @@ impl Block {
 }
 
 impl Codec for Block {
-    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
         Ok(self.inner.clone())
     }
 
-    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
         Ok(Block { inner: data })
     }
 }
@@ -71,7 +73,7 @@ impl Consensus<Block> for Adapter {
         &self,
         _ctx: Context,
         _height: u64,
-    ) -> Result<(Block, Hash), Box<dyn Error + Send>> {
+    ) -> Result<(Block, Hash), Box<dyn Error + Send + Sync>> {
         let content = gen_random_bytes();
         Ok((Block::from(content.clone()), hash(&content)))
     }
@@ -82,7 +84,7 @@ impl Consensus<Block> for Adapter {
         _height: u64,
         _hash: Hash,
         _block: Block,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -91,13 +93,13 @@ impl Consensus<Block> for Adapter {
         _ctx: Context,
         height: u64,
         commit: Commit<Block>,
-    ) -> Result<Status, Box<dyn Error + Send>> {
-        let status = Status {
-            height:         height + 1,
-            interval:       Some(self.records.interval),
-            timer_config:   None,
-            authority_list: self.records.node_record.clone(),
-        };
+    ) -> Result<Status, Box<dyn Error + Send + Sync>> {
+        let status = Status::new(
+            height + 1,
+            Some(self.records.interval),
+            None,
+            self.records.node_record.clone(),
+        );
 
         let commit_block_hash = hash(&commit.content.inner);
 
@@ -149,7 +151,7 @@ impl Consensus<Block> for Adapter {
         &self,
         _ctx: Context,
         _height: u64,
-    ) -> Result<Vec<Node>, Box<dyn Error + Send>> {
+    ) -> Result<Vec<Node>, Box<dyn Error + Send + Sync>> {
         Ok(self.records.node_record.clone())
     }
 
@@ -157,7 +159,7 @@ impl Consensus<Block> for Adapter {
         &self,
         _ctx: Context,
         words: OverlordMsg<Block>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         self.talk_to.iter().for_each(|(_, mouth)| {
             let _ = mouth.send(words.clone());
         });
@@ -169,7 +171,7 @@ impl Consensus<Block> for Adapter {
         _ctx: Context,
         address: Bytes,
         words: OverlordMsg<Block>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         if let Some(sender) = self.talk_to.get(&address) {
             let _ = sender.send(words);
         }
@@ -177,6 +179,10 @@ impl Consensus<Block> for Adapter {
     }
 
     fn report_error(&self, _ctx: Context, _err: ConsensusError) {}
+
+    fn report_evidence(&self, _ctx: Context, _evidence: Evidence<Block>) {}
+
+    fn report_penalty(&self, _ctx: Context, _penalty: PeerPenalty) {}
 }
 
 pub struct Participant {
@@ -204,18 +210,20 @@ impl Participant {
             Arc::clone(&adapter),
             Arc::new(crypto),
             Arc::new(records.wal_record.get(address).unwrap().clone()),
+            Arc::new(RoundRobin),
+            Arc::new(TokioSpawner),
         );
         let overlord_handler = overlord.get_handler();
 
         overlord_handler
             .send_msg(
                 Context::new(),
-                OverlordMsg::RichStatus(Status {
-                    height:         1,
-                    interval:       Some(records.interval),
-                    timer_config:   timer_config(),
-                    authority_list: records.node_record,
-                }),
+                OverlordMsg::RichStatus(Status::new(
+                    1,
+                    Some(records.interval),
+                    timer_config(),
+                    records.node_record,
+                )),
             )
             .unwrap();
 
@@ -231,7 +239,7 @@ impl Participant {
         interval: u64,
         timer_config: Option<DurationConfig>,
         node_list: Vec<Node>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let adapter = Arc::<Adapter>::clone(&self.adapter);
         let handler = self.handler.clone();
 
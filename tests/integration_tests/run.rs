@@ -119,6 +119,29 @@ fn run_alive_nodes(
     )
 }
 
+/// Run a single consensus node of the records, return the participant with the sender of its
+/// hearing channel.
+pub fn run_single_node(records: &Record) -> (Arc<Participant>, Sender<OverlordMsg<Block>>) {
+    let records = records.as_internal();
+    let interval = records.interval;
+    let address = records.node_record[0].address.clone();
+    let (sender, receiver) = unbounded();
+
+    let node = Arc::new(Participant::new(
+        &address,
+        HashMap::new(),
+        receiver,
+        records.clone(),
+    ));
+
+    let runner = Arc::clone(&node);
+    let list = records.node_record.clone();
+    tokio::spawn(async move {
+        runner.run(interval, timer_config(), list).await.unwrap();
+    });
+    (node, sender)
+}
+
 fn synchronize_height(
     records: &Record,
     alive_nodes: Vec<Node>,
@@ -148,12 +171,12 @@ fn synchronize_height(
                         );
                         let _ = node.handler.send_msg(
                             Context::new(),
-                            OverlordMsg::RichStatus(Status {
-                                height:         max_height + 1,
-                                interval:       Some(interval),
-                                timer_config:   timer_config(),
-                                authority_list: node_record.clone(),
-                            }),
+                            OverlordMsg::RichStatus(Status::new(
+                                max_height + 1,
+                                Some(interval),
+                                timer_config(),
+                                node_record.clone(),
+                            )),
                         );
                     });
             }
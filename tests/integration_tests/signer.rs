@@ -0,0 +1,58 @@
+use std::error::Error;
+use std::io::{Error as IoError, ErrorKind};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::channel::{mpsc, oneshot};
+use futures::{FutureExt, StreamExt};
+
+use overlord::types::{Hash, Signature};
+use overlord::{Clock, Crypto, Signer, Spawner};
+
+type SignRequest = (Hash, oneshot::Sender<Result<Signature, String>>);
+
+/// A signer which sends the signing requests to a signer service and awaits the signatures, as
+/// a remote KMS or an HSM does.
+pub struct RemoteSigner {
+    requests: mpsc::UnboundedSender<SignRequest>,
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send + Sync>> {
+        let (tx, rx) = oneshot::channel();
+        self.requests
+            .unbounded_send((hash, tx))
+            .map_err(|_| signer_error("signer service is closed".to_string()))?;
+        rx.await
+            .map_err(|_| signer_error("signer service drops the request".to_string()))?
+            .map_err(signer_error)
+    }
+}
+
+/// Spawn a stand-in of a remote signer service, which signs by the crypto after the latency, and
+/// return a signer connected to it. The service exits when the signer is dropped.
+pub fn spawn_signer_service<C: Crypto + Sync + 'static>(
+    crypto: C,
+    clock: Arc<dyn Clock>,
+    spawner: &dyn Spawner,
+    latency: Duration,
+) -> RemoteSigner {
+    let (tx, mut rx) = mpsc::unbounded::<SignRequest>();
+    spawner.spawn(
+        async move {
+            while let Some((hash, resp)) = rx.next().await {
+                clock.delay(latency).await;
+                let signature = Crypto::sign(&crypto, hash).map_err(|e| e.to_string());
+                let _ = resp.send(signature);
+            }
+        }
+        .boxed(),
+    );
+    RemoteSigner { requests: tx }
+}
+
+fn signer_error(msg: String) -> Box<dyn Error + Send + Sync> {
+    Box::new(IoError::new(ErrorKind::Other, msg))
+}
@@ -0,0 +1,549 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use creep::Context;
+use futures::FutureExt;
+
+use overlord::election::RoundRobin;
+use overlord::error::ConsensusError;
+use overlord::sim::{SimNetwork, Simulator};
+use overlord::types::{
+    Address, Commit, Evidence, Hash, Misbehavior, Node, OverlordMsg, PeerPenalty, SignedVote,
+    Status, Vote, VoteType,
+};
+use overlord::{
+    with_peer, Codec, Consensus, Crypto, Overlord, OverlordHandler, Signer, Spawner, Wal,
+};
+
+use super::crypto::MockCrypto;
+use super::signer::spawn_signer_service;
+use super::utils::{hash, timer_config};
+
+const INTERVAL: u64 = 100;
+
+type CommitLog = Vec<(u64, Hash, Duration)>;
+type VerifyRecord = (Hash, Bytes, Vec<Address>);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Pill {
+    inner: Bytes,
+}
+
+impl Codec for Pill {
+    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+        Ok(self.inner.clone())
+    }
+
+    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Pill { inner: data })
+    }
+}
+
+struct SimAdapter {
+    address:        Address,
+    authority_list: Vec<Node>,
+    network:        SimNetwork<Pill>,
+    simulator:      Simulator,
+    commits:        Arc<Mutex<CommitLog>>,
+    penalties:      Arc<Mutex<Vec<PeerPenalty>>>,
+}
+
+#[async_trait]
+impl Consensus<Pill> for SimAdapter {
+    async fn get_block(
+        &self,
+        _ctx: Context,
+        height: u64,
+    ) -> Result<(Pill, Hash), Box<dyn Error + Send + Sync>> {
+        let content = Bytes::from(format!("block {} from {:?}", height, self.address));
+        Ok((
+            Pill {
+                inner: content.clone(),
+            },
+            hash(&content),
+        ))
+    }
+
+    async fn check_block(
+        &self,
+        _ctx: Context,
+        _height: u64,
+        _hash: Hash,
+        _block: Pill,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn commit(
+        &self,
+        _ctx: Context,
+        height: u64,
+        commit: Commit<Pill>,
+    ) -> Result<Status, Box<dyn Error + Send + Sync>> {
+        self.commits.lock().unwrap().push((
+            commit.height,
+            hash(&commit.content.inner),
+            self.simulator.elapsed(),
+        ));
+        Ok(Status::new(
+            height + 1,
+            Some(INTERVAL),
+            None,
+            self.authority_list.clone(),
+        ))
+    }
+
+    async fn get_authority_list(
+        &self,
+        _ctx: Context,
+        _height: u64,
+    ) -> Result<Vec<Node>, Box<dyn Error + Send + Sync>> {
+        Ok(self.authority_list.clone())
+    }
+
+    async fn broadcast_to_other(
+        &self,
+        _ctx: Context,
+        words: OverlordMsg<Pill>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.network.broadcast(&self.address, words);
+        Ok(())
+    }
+
+    async fn transmit_to_relayer(
+        &self,
+        _ctx: Context,
+        address: Address,
+        words: OverlordMsg<Pill>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.network.transmit(&self.address, &address, words);
+        Ok(())
+    }
+
+    fn report_error(&self, _ctx: Context, _err: ConsensusError) {}
+
+    fn report_evidence(&self, _ctx: Context, _evidence: Evidence<Pill>) {}
+
+    fn report_penalty(&self, _ctx: Context, penalty: PeerPenalty) {
+        self.penalties.lock().unwrap().push(penalty);
+    }
+}
+
+/// A wal in memory, whose saves fail when it is broken.
+#[derive(Default)]
+struct MemWal {
+    content: Mutex<Option<Bytes>>,
+    broken:  AtomicBool,
+}
+
+impl MemWal {
+    fn set_broken(&self, broken: bool) {
+        self.broken.store(broken, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Wal for MemWal {
+    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.broken.load(Ordering::SeqCst) {
+            return Err(Box::new(ConsensusError::Other("wal is broken".to_string())));
+        }
+        *self.content.lock().unwrap() = Some(info);
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send + Sync>> {
+        Ok(self.content.lock().unwrap().clone())
+    }
+}
+
+/// A crypto which records the verified messages, signatures and signers.
+struct RecordCrypto {
+    inner:    MockCrypto,
+    verified: Arc<Mutex<Vec<VerifyRecord>>>,
+}
+
+impl Crypto for RecordCrypto {
+    fn hash(&self, msg: Bytes) -> Bytes {
+        self.inner.hash(msg)
+    }
+
+    fn sign(&self, hash: Bytes) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+        self.inner.sign(hash)
+    }
+
+    fn aggregate_signatures(
+        &self,
+        signatures: Vec<Bytes>,
+        voters: Vec<Bytes>,
+    ) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+        self.inner.aggregate_signatures(signatures, voters)
+    }
+
+    fn verify_signature(
+        &self,
+        signature: Bytes,
+        hash: Bytes,
+        voter: Bytes,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.verified
+            .lock()
+            .unwrap()
+            .push((hash.clone(), signature.clone(), vec![voter.clone()]));
+        self.inner.verify_signature(signature, hash, voter)
+    }
+
+    fn verify_aggregated_signature(
+        &self,
+        aggregated_signature: Bytes,
+        hash: Bytes,
+        voters: Vec<Bytes>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.verified.lock().unwrap().push((
+            hash.clone(),
+            aggregated_signature.clone(),
+            voters.clone(),
+        ));
+        self.inner
+            .verify_aggregated_signature(aggregated_signature, hash, voters)
+    }
+}
+
+/// A signer which counts the signatures it makes.
+struct CountSigner {
+    crypto: MockCrypto,
+    count:  Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Signer for CountSigner {
+    async fn sign(&self, hash: Hash) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        Crypto::sign(&self.crypto, hash)
+    }
+}
+
+type SimOverlord = Overlord<Pill, SimAdapter, RecordCrypto, MemWal>;
+
+struct SimNode {
+    overlord:  Arc<SimOverlord>,
+    wal:       Arc<MemWal>,
+    handler:   OverlordHandler<Pill>,
+    commits:   Arc<Mutex<CommitLog>>,
+    penalties: Arc<Mutex<Vec<PeerPenalty>>>,
+    verified:  Arc<Mutex<Vec<VerifyRecord>>>,
+    exit:      Arc<Mutex<Option<bool>>>,
+}
+
+impl SimNode {
+    fn run(&self, simulator: &Simulator, authority_list: Vec<Node>) {
+        let overlord = Arc::clone(&self.overlord);
+        let exit = Arc::clone(&self.exit);
+        *exit.lock().unwrap() = None;
+        simulator.spawn(
+            async move {
+                let res = overlord.run(INTERVAL, authority_list, timer_config()).await;
+                *exit.lock().unwrap() = Some(res.is_ok());
+            }
+            .boxed(),
+        );
+    }
+
+    fn send_status(&self, height: u64, authority_list: Vec<Node>) {
+        self.handler
+            .send_msg(
+                Context::new(),
+                OverlordMsg::RichStatus(Status::new(
+                    height,
+                    Some(INTERVAL),
+                    timer_config(),
+                    authority_list,
+                )),
+            )
+            .unwrap();
+    }
+
+    fn latest_height(&self) -> u64 {
+        self.commits
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(height, _, _)| *height)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Start `num` overlord instances in a simulator of the seed.
+fn start(seed: u64, num: u8) -> (Simulator, BTreeMap<Address, SimNode>) {
+    start_with(seed, num, |_, _, _| ())
+}
+
+/// Start `num` overlord instances in a simulator of the seed, each is set up before running.
+fn start_with<S>(seed: u64, num: u8, setup: S) -> (Simulator, BTreeMap<Address, SimNode>)
+where
+    S: Fn(&Simulator, &Address, &SimOverlord),
+{
+    let simulator = Simulator::new(seed);
+    let network = SimNetwork::new(
+        &simulator,
+        Duration::from_millis(1),
+        Duration::from_millis(20),
+    );
+    let authority_list = (0..num)
+        .map(|i| Node::new(Bytes::from(vec![i; 32])))
+        .collect::<Vec<_>>();
+
+    let mut nodes = BTreeMap::new();
+    for node in authority_list.iter() {
+        let address = node.address.clone();
+        let commits = Arc::new(Mutex::new(Vec::new()));
+        let penalties = Arc::new(Mutex::new(Vec::new()));
+        let verified = Arc::new(Mutex::new(Vec::new()));
+        let wal = Arc::new(MemWal::default());
+        let adapter = SimAdapter {
+            address:        address.clone(),
+            authority_list: authority_list.clone(),
+            network:        network.clone(),
+            simulator:      simulator.clone(),
+            commits:        Arc::clone(&commits),
+            penalties:      Arc::clone(&penalties),
+        };
+        let overlord = Overlord::with_runtime(
+            address.clone(),
+            Arc::new(adapter),
+            Arc::new(RecordCrypto {
+                inner:    MockCrypto::new(address.clone()),
+                verified: Arc::clone(&verified),
+            }),
+            Arc::clone(&wal),
+            Arc::new(RoundRobin),
+            simulator.clock(),
+            simulator.spawner(),
+        );
+        setup(&simulator, &address, &overlord);
+        let handler = overlord.get_handler();
+        network.register(address.clone(), handler.clone());
+
+        let node = SimNode {
+            overlord: Arc::new(overlord),
+            wal,
+            handler,
+            commits,
+            penalties,
+            verified,
+            exit: Arc::new(Mutex::new(None)),
+        };
+        node.send_status(1, authority_list.clone());
+        node.run(&simulator, authority_list.clone());
+        nodes.insert(address, node);
+    }
+    (simulator, nodes)
+}
+
+fn stop(simulator: &Simulator, nodes: &BTreeMap<Address, SimNode>) {
+    for node in nodes.values() {
+        node.handler
+            .send_msg(Context::new(), OverlordMsg::Stop)
+            .unwrap();
+    }
+    simulator.run_for(Duration::from_secs(60));
+
+    for node in nodes.values() {
+        assert_eq!(*node.exit.lock().unwrap(), Some(true));
+    }
+    // All the internal tasks of overlord exit.
+    assert_eq!(simulator.task_count(), 0);
+}
+
+/// Run the simulator until all the overlord instances commit `target_height`.
+fn run_to_height(simulator: &Simulator, nodes: &BTreeMap<Address, SimNode>, target_height: u64) {
+    let finished = simulator.run_until(Duration::from_secs(60), || {
+        nodes.values().all(|node| {
+            node.commits
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|(height, _, _)| *height >= target_height)
+        })
+    });
+    assert!(finished, "consensus stagnated in the simulation");
+}
+
+/// Run `num` overlord instances in a simulator of the seed until all of them commit
+/// `target_height`, and return the commit logs.
+fn simulate(seed: u64, num: u8, target_height: u64) -> BTreeMap<Address, CommitLog> {
+    let (simulator, nodes) = start(seed, num);
+    run_to_height(&simulator, &nodes, target_height);
+    nodes
+        .into_iter()
+        .map(|(address, node)| {
+            let commits = node.commits.lock().unwrap().clone();
+            (address, commits)
+        })
+        .collect()
+}
+
+#[test]
+fn test_sim_consistency() {
+    let logs = simulate(1, 4, 10);
+    let mut committed = BTreeMap::new();
+    for commits in logs.values() {
+        for (height, block_hash, _) in commits.iter() {
+            assert_eq!(
+                committed
+                    .entry(*height)
+                    .or_insert_with(|| block_hash.clone()),
+                block_hash
+            );
+        }
+    }
+}
+
+#[test]
+fn test_sim_determinism() {
+    assert_eq!(simulate(42, 4, 5), simulate(42, 4, 5));
+}
+
+#[test]
+fn test_sim_stop() {
+    let (simulator, nodes) = start(7, 4);
+    run_to_height(&simulator, &nodes, 3);
+    stop(&simulator, &nodes);
+}
+
+#[test]
+fn test_sim_restart() {
+    let (simulator, nodes) = start(11, 4);
+    run_to_height(&simulator, &nodes, 3);
+    stop(&simulator, &nodes);
+
+    // Restart all the instances from the same height as if they have synchronized the state. The
+    // handlers got before the restart keep working.
+    let authority_list = nodes
+        .keys()
+        .map(|address| Node::new(address.clone()))
+        .collect::<Vec<_>>();
+    let height = nodes.values().map(SimNode::latest_height).max().unwrap();
+    for node in nodes.values() {
+        node.send_status(height + 1, authority_list.clone());
+        node.run(&simulator, authority_list.clone());
+    }
+    run_to_height(&simulator, &nodes, height + 3);
+    stop(&simulator, &nodes);
+}
+
+#[test]
+fn test_sim_penalty() {
+    let (simulator, nodes) = start(3, 4);
+    run_to_height(&simulator, &nodes, 2);
+
+    // A peer relays a future vote signed by an address out of the authority list.
+    let peer = Bytes::from(vec![9u8; 32]);
+    let node = nodes.values().next().unwrap();
+    let vote = SignedVote {
+        signature: Bytes::from(vec![9u8; 32]),
+        vote:      Vote {
+            height:     node.latest_height() + 2,
+            round:      0,
+            vote_type:  VoteType::Prevote,
+            block_hash: hash(&Bytes::from("fake block")),
+        },
+        voter:     Bytes::from(vec![9u8; 32]),
+    };
+    node.handler
+        .send_msg(
+            with_peer(&Context::new(), peer.clone()),
+            OverlordMsg::SignedVote(vote),
+        )
+        .unwrap();
+    simulator.run_for(Duration::from_secs(1));
+
+    let penalties = node
+        .penalties
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|penalty| penalty.peer == peer)
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(penalties, vec![PeerPenalty {
+        peer,
+        misbehavior: Misbehavior::UnknownVoter,
+        count: 1,
+        score: Misbehavior::UnknownVoter.weight(),
+    }]);
+    stop(&simulator, &nodes);
+}
+
+#[test]
+fn test_sim_verify_once() {
+    let (simulator, nodes) = start(5, 4);
+    run_to_height(&simulator, &nodes, 5);
+
+    // The repeated messages, such as the QCs in chokes and PoLCs, hit the verify cache.
+    for node in nodes.values() {
+        let verified = node.verified.lock().unwrap();
+        let distinct = verified.iter().collect::<HashSet<_>>();
+        assert!(!verified.is_empty());
+        assert_eq!(distinct.len(), verified.len());
+    }
+    stop(&simulator, &nodes);
+}
+
+#[test]
+fn test_sim_remote_signer() {
+    let (simulator, nodes) = start_with(13, 4, |simulator, address, overlord| {
+        let signer = spawn_signer_service(
+            MockCrypto::new(address.clone()),
+            simulator.clock(),
+            simulator,
+            Duration::from_millis(5),
+        );
+        overlord.set_signer(Arc::new(signer));
+    });
+    run_to_height(&simulator, &nodes, 5);
+}
+
+#[test]
+fn test_sim_wal_failure() {
+    let counts = Mutex::new(BTreeMap::new());
+    let (simulator, nodes) = start_with(17, 4, |_, address, overlord| {
+        let count = Arc::new(AtomicUsize::new(0));
+        counts
+            .lock()
+            .unwrap()
+            .insert(address.clone(), Arc::clone(&count));
+        overlord.set_signer(Arc::new(CountSigner {
+            crypto: MockCrypto::new(address.clone()),
+            count,
+        }));
+    });
+    run_to_height(&simulator, &nodes, 2);
+
+    // An instance never signs while its wal fails to save the signing watermark, even when it
+    // retries the same step.
+    let (address, node) = nodes.iter().next().unwrap();
+    let count = Arc::clone(&counts.lock().unwrap()[address]);
+    node.wal.set_broken(true);
+    let signed = count.load(Ordering::SeqCst);
+    simulator.run_for(Duration::from_secs(10));
+    assert_eq!(count.load(Ordering::SeqCst), signed);
+
+    // It signs again once the wal saves, after it synchronizes to the latest height.
+    node.wal.set_broken(false);
+    let authority_list = nodes
+        .keys()
+        .map(|address| Node::new(address.clone()))
+        .collect::<Vec<_>>();
+    let height = nodes.values().map(SimNode::latest_height).max().unwrap();
+    node.send_status(height + 1, authority_list);
+    run_to_height(&simulator, &nodes, height + 3);
+    assert!(count.load(Ordering::SeqCst) > signed);
+    stop(&simulator, &nodes);
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use creep::Context;
+use futures::future::{self, Either};
+use futures_timer::Delay;
+
+use overlord::types::OverlordMsg;
+
+use super::run::run_single_node;
+use super::wal::Record;
+
+#[tokio::test(threaded_scheduler)]
+async fn test_status_query() {
+    let records = Record::new(1, 10);
+    let (node, sender) = run_single_node(&records);
+
+    // Poll the status until the node goes beyond the first height.
+    let poll = async {
+        loop {
+            let snapshot = node.handler.status().await.unwrap();
+            if snapshot.height > 1 {
+                return snapshot;
+            }
+            Delay::new(Duration::from_millis(10)).await;
+        }
+    };
+    let timeout = Delay::new(Duration::from_secs(30));
+    let snapshot = match future::select(Box::pin(poll), timeout).await {
+        Either::Left((snapshot, _)) => snapshot,
+        Either::Right(_) => panic!("the node does not go beyond the first height in 30 seconds"),
+    };
+    assert!(snapshot.is_leader);
+    assert_eq!(snapshot.leader_address, node.adapter.address);
+
+    node.handler
+        .send_msg(Context::new(), OverlordMsg::Stop)
+        .unwrap();
+    sender.send(OverlordMsg::Stop).unwrap();
+}
@@ -7,7 +7,6 @@ use std::sync::{Arc, Mutex};
 use async_trait::async_trait;
 use bytes::Bytes;
 use lru_cache::LruCache;
-use rlp::Encodable;
 use serde::{Deserialize, Serialize};
 use serde_json;
 
@@ -45,11 +44,11 @@ impl MockWal {
 
 #[async_trait]
 impl Wal for MockWal {
-    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send>> {
+    async fn save(&self, info: Bytes) -> Result<(), Box<dyn Error + Send + Sync>> {
         let test_id_updated = *self.test_id_updated.lock().unwrap();
         // avoid previous test overwrite wal of the latest test
         if test_id_updated == self.test_id {
-            // let content: WalInfo<Block> = rlp::decode(&info).unwrap();
+            // let content = WalInfo::<Block>::from_wal_bytes(&info, &[]).unwrap();
             // println!("{:?} save {:?}", to_hex(&self.address), content);
             *self.content.lock().unwrap() = Some(info);
         } else {
@@ -58,10 +57,10 @@ impl Wal for MockWal {
         Ok(())
     }
 
-    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send>> {
+    async fn load(&self) -> Result<Option<Bytes>, Box<dyn Error + Send + Sync>> {
         let info = self.content.lock().unwrap().as_ref().cloned();
         if let Some(info) = info.clone() {
-            let content: WalInfo<Block> = rlp::decode(&info).unwrap();
+            let content = WalInfo::<Block>::from_wal_bytes(&info, &[]).unwrap();
             println!("{:?} load {:?}", to_hex(&self.address), content);
         }
         Ok(info)
@@ -130,7 +129,7 @@ impl Record {
                         .lock()
                         .unwrap()
                         .as_ref()
-                        .map(|wal| rlp::decode(&wal).unwrap()),
+                        .map(|wal| WalInfo::from_wal_bytes(&wal, &node_record).unwrap()),
                 )
             })
             .collect();
@@ -247,7 +246,7 @@ impl RecordInternal {
                         .lock()
                         .unwrap()
                         .as_ref()
-                        .map(|wal| rlp::decode(&wal).unwrap()),
+                        .map(|wal| WalInfo::from_wal_bytes(&wal, &node_record).unwrap()),
                 )
             })
             .collect();
@@ -321,7 +320,7 @@ impl RecordForWal {
                     test_id_updated: Arc::clone(&test_id),
                     address:         address.clone(),
                     content:         Arc::new(Mutex::new(
-                        wal.as_ref().map(|wal| Bytes::from(wal.rlp_bytes())),
+                        wal.as_ref().map(|wal| wal.to_wal_bytes()),
                     )),
                 })
             })
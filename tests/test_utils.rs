@@ -7,7 +7,9 @@ use bytes::{Bytes, BytesMut};
 use creep::Context;
 use crossbeam_channel::Sender;
 use overlord::error::ConsensusError;
-use overlord::types::{Address, Commit, Hash, Node, OverlordMsg, Signature, Status};
+use overlord::types::{
+    Address, Commit, Evidence, Hash, Node, OverlordMsg, PeerPenalty, Signature, Status,
+};
 use overlord::{Codec, Consensus, Crypto};
 use rand::random;
 use serde::{Deserialize, Serialize};
@@ -24,12 +26,12 @@ struct Pill {
 }
 
 impl Codec for Pill {
-    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send>> {
+    fn encode(&self) -> Result<Bytes, Box<dyn Error + Send + Sync>> {
         let encode: Vec<u8> = serialize(&self).expect("Serialize Pill error");
         Ok(Bytes::from(encode))
     }
 
-    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send>> {
+    fn decode(data: Bytes) -> Result<Self, Box<dyn Error + Send + Sync>> {
         let decode: Pill = deserialize(&data.as_ref()).expect("Deserialize Pill error.");
         Ok(decode)
     }
@@ -54,7 +56,7 @@ impl Consensus<Pill> for ConsensusHelper<Pill> {
         &self,
         _ctx: Context,
         height: u64,
-    ) -> Result<(Pill, Hash), Box<dyn Error + Send>> {
+    ) -> Result<(Pill, Hash), Box<dyn Error + Send + Sync>> {
         let epoch = Pill::new(height);
         let hash = BytesMut::from(blake2b(epoch.clone().encode()?.as_ref()).as_bytes()).freeze();
         Ok((epoch, hash))
@@ -66,7 +68,7 @@ impl Consensus<Pill> for ConsensusHelper<Pill> {
         _height: u64,
         _hash: Hash,
         _epoch: Pill,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -75,14 +77,9 @@ impl Consensus<Pill> for ConsensusHelper<Pill> {
         _ctx: Context,
         height: u64,
         commit: Commit<Pill>,
-    ) -> Result<Status, Box<dyn Error + Send>> {
+    ) -> Result<Status, Box<dyn Error + Send + Sync>> {
         self.commit_tx.send(commit).unwrap();
-        let status = Status {
-            height:         height + 1,
-            interval:       None,
-            timer_config:   None,
-            authority_list: self.auth_list.clone(),
-        };
+        let status = Status::new(height + 1, None, None, self.auth_list.clone());
         Ok(status)
     }
 
@@ -90,7 +87,7 @@ impl Consensus<Pill> for ConsensusHelper<Pill> {
         &self,
         _ctx: Context,
         _height: u64,
-    ) -> Result<Vec<Node>, Box<dyn Error + Send>> {
+    ) -> Result<Vec<Node>, Box<dyn Error + Send + Sync>> {
         Ok(self.auth_list.clone())
     }
 
@@ -98,7 +95,7 @@ impl Consensus<Pill> for ConsensusHelper<Pill> {
         &self,
         _ctx: Context,
         msg: OverlordMsg<Pill>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let message = Msg {
             content:  msg,
             approach: Approach::Broadcast,
@@ -113,7 +110,7 @@ impl Consensus<Pill> for ConsensusHelper<Pill> {
         _ctx: Context,
         addr: Address,
         msg: OverlordMsg<Pill>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let message = Msg {
             content:  msg,
             approach: Approach::Directly(addr),
@@ -124,6 +121,10 @@ impl Consensus<Pill> for ConsensusHelper<Pill> {
     }
 
     fn report_error(&self, _ctx: Context, _err: ConsensusError) {}
+
+    fn report_evidence(&self, _ctx: Context, _evidence: Evidence<Pill>) {}
+
+    fn report_penalty(&self, _ctx: Context, _penalty: PeerPenalty) {}
 }
 
 #[derive(Clone)]
@@ -134,7 +135,7 @@ impl Crypto for BlsCrypto {
         self.0.clone()
     }
 
-    fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send>> {
+    fn sign(&self, hash: Hash) -> Result<Signature, Box<dyn Error + Send + Sync>> {
         Ok(hash)
     }
 
@@ -143,7 +144,7 @@ impl Crypto for BlsCrypto {
         _signature: Signature,
         _hash: Hash,
         _voter: Address,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 
@@ -151,7 +152,7 @@ impl Crypto for BlsCrypto {
         &self,
         _signatures: Vec<Signature>,
         _voters: Vec<Address>,
-    ) -> Result<Signature, Box<dyn Error + Send>> {
+    ) -> Result<Signature, Box<dyn Error + Send + Sync>> {
         Ok(gen_hash())
     }
 
@@ -160,7 +161,7 @@ impl Crypto for BlsCrypto {
         _aggregate_signature: Signature,
         _hash: Hash,
         _voters: Vec<Address>,
-    ) -> Result<(), Box<dyn Error + Send>> {
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         Ok(())
     }
 }